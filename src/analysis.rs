@@ -0,0 +1,713 @@
+//! Shallow analysis of the mirrored board, for questions cold_clear's search tree can't answer
+//! through its public interface.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use libtetris::*;
+use cold_clear::evaluation::{Evaluator, Standard};
+use serde::{Serialize, Deserialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// A placement the bot could make right now, along with its immediate outcome.
+pub(crate) struct Outcome {
+    pub(crate) placement: Placement,
+    pub(crate) piece: Piece,
+    pub(crate) hold: bool,
+    pub(crate) lock: LockResult,
+    /// The board after the placement, with the queue and hold slot advanced accordingly.
+    pub(crate) board: Board
+}
+
+impl Outcome {
+    /// The move that makes this placement, as cold_clear would have provided it.
+    pub(crate) fn to_move(&self) -> cold_clear::Move {
+        cold_clear::Move {
+            inputs: self.placement.inputs.movements.iter().copied().collect(),
+            expected_location: self.placement.location,
+            hold: self.hold
+        }
+    }
+}
+
+/// An outcome along with its depth-1 evaluation.
+pub(crate) struct Candidate {
+    pub(crate) outcome: Outcome,
+    pub(crate) eval: i32
+}
+
+/// A placement in a sequence found by one of the searches in this module.
+#[derive(Serialize)]
+pub(crate) struct Step {
+    pub(crate) location: FallingPiece,
+    pub(crate) cells: [(i32, i32); 4],
+    pub(crate) hold: bool
+}
+
+impl Step {
+    fn new(outcome: &Outcome) -> Self {
+        let location = outcome.placement.location;
+        Self { location, cells: location.cells(), hold: outcome.hold }
+    }
+}
+
+/// A placement the bot could make right now, with its depth-1 evaluation.
+#[derive(Serialize)]
+pub(crate) struct ScoredPlacement {
+    #[serde(flatten)]
+    pub(crate) step: Step,
+    pub(crate) eval: i32
+}
+
+impl ScoredPlacement {
+    pub(crate) fn new(candidate: &Candidate) -> Self {
+        Self { step: Step::new(&candidate.outcome), eval: candidate.eval }
+    }
+}
+
+/// The quickest way cold_clear knows to input a placement.
+#[derive(Serialize)]
+pub(crate) struct Finesse {
+    pub(crate) inputs: Vec<PieceMovement>,
+    /// How long the inputs take, as cold_clear's movement generation estimates it.
+    pub(crate) time: u32,
+    pub(crate) hold: bool
+}
+
+/// Finds the quickest input sequence cold_clear's movement generation produces that leaves
+/// `placement`'s cells filled, using the number of inputs to break ties.
+///
+/// The movement generation only keeps the quickest path it finds to each piece position, so this
+/// isn't a search for the fewest inputs, only a choice between the rotation states that fill the
+/// same cells.
+pub(crate) fn finesse(
+    board: &Board,
+    options: &cold_clear::Options,
+    placement: &FallingPiece
+) -> Option<Finesse> {
+    outcomes(board, options)
+        .into_iter()
+        .filter(|outcome| same_placement(&outcome.placement.location, placement))
+        .min_by_key(|outcome| {
+            let inputs = &outcome.placement.inputs;
+            (inputs.time, inputs.movements.len())
+        })
+        .map(|outcome| Finesse {
+            inputs: outcome.placement.inputs.movements.iter().copied().collect(),
+            time: outcome.placement.inputs.time,
+            hold: outcome.hold
+        })
+}
+
+#[derive(Serialize)]
+pub(crate) struct MoveRank {
+    /// 0 is the placement the evaluator likes best.
+    pub(crate) rank: usize,
+    pub(crate) eval: i32,
+    pub(crate) candidates: usize
+}
+
+/// cold_clear scores a placement as the value of the resulting board plus a reward for how it got
+/// there. The sum is what bots compare placements by at the first level of the search.
+pub(crate) fn score(
+    evaluator: &Standard,
+    lock: &LockResult,
+    board: &Board,
+    move_time: u32,
+    piece: Piece
+) -> i32 {
+    let (value, reward) = evaluator.evaluate(lock, board, move_time, piece);
+    value.value + reward.value
+}
+
+/// The evaluator's opinion of `board` as it stands, without the reward for any placement.
+pub(crate) fn board_value(board: &Board, evaluator: &Standard) -> i32 {
+    // The evaluator always scores a board together with the placement that produced it. Nothing
+    // was placed here, so it is given a lock result for a placement that did nothing, and only
+    // the board's value is kept.
+    let nothing = LockResult {
+        placement_kind: PlacementKind::None,
+        locked_out: false,
+        b2b: false,
+        perfect_clear: false,
+        combo: None,
+        garbage_sent: 0,
+        cleared_lines: Default::default()
+    };
+    let piece = board.next_queue().next().unwrap_or(Piece::T);
+    let (value, _) = evaluator.evaluate(&nothing, board, 0, piece);
+    value.value
+}
+
+/// The part of the evaluator's value of `board` that comes from its T-slot weights.
+pub(crate) fn tslot_value(board: &Board, evaluator: &Standard) -> i32 {
+    // Standard doesn't expose its terms separately, but each is its weight times what it measures,
+    // so the T-slot term is what the value loses without the T-slot weights.
+    let without_tslots = Standard { tslot: [0; 4], ..evaluator.clone() };
+    board_value(board, evaluator) - board_value(board, &without_tslots)
+}
+
+/// How many T-slots clearing 0, 1, 2 and 3 lines the evaluator finds on `board`.
+pub(crate) fn tslot_counts(board: &Board, evaluator: &Standard) -> [i32; 4] {
+    // As in `tslot_value`, but with a weight of 1 for one kind of slot at a time.
+    let without_tslots = Standard { tslot: [0; 4], ..evaluator.clone() };
+    let base = board_value(board, &without_tslots);
+    let mut counts = [0; 4];
+    for (lines, count) in counts.iter_mut().enumerate() {
+        let mut tslot = [0; 4];
+        tslot[lines] = 1;
+        let one_kind = Standard { tslot, ..evaluator.clone() };
+        *count = board_value(board, &one_kind) - base;
+    }
+    counts
+}
+
+/// Pushes the field up by a row of garbage for each of `holes`, the bottom row getting the last
+/// hole.
+///
+/// Returns false if that pushed filled cells off the top of the field.
+pub(crate) fn add_garbage(board: &mut Board, holes: &[usize]) -> bool {
+    let field = board.get_field();
+    let lines = holes.len().min(field.len());
+    let survived = field[field.len() - lines..].iter().all(|row| row.iter().all(|&c| !c));
+    let mut raised = [[true; 10]; 40];
+    for (row, &hole) in raised.iter_mut().zip(holes.iter().rev()) {
+        row[hole] = false;
+    }
+    raised[lines..].copy_from_slice(&field[..field.len() - lines]);
+    board.set_field(raised);
+    survived
+}
+
+/// How many placements of `plan` it takes to clear every row of `board` with garbage in it,
+/// going by `cells`, or `None` if the plan never does.
+pub(crate) fn downstack_length(
+    board: &Board,
+    cells: &[[u8; 10]; 40],
+    plan: &[FallingPiece]
+) -> Option<usize> {
+    let mut garbage: Vec<bool> = cells.iter()
+        .map(|row| row.contains(&crate::worker::GARBAGE_CELL))
+        .collect();
+    let mut board = board.clone();
+    for (placed, &placement) in (0..).zip(plan) {
+        if !garbage.contains(&true) {
+            return Some(placed);
+        }
+        let mut cleared: Vec<_> = board.lock_piece(placement).cleared_lines
+            .iter()
+            .map(|&y| y as usize)
+            .collect();
+        cleared.sort_by(|a, b| b.cmp(a));
+        for y in cleared {
+            garbage.remove(y);
+            garbage.push(false);
+        }
+    }
+    match garbage.contains(&true) {
+        true => None,
+        false => Some(plan.len())
+    }
+}
+
+/// Board values at or below this count as hopeless for `strength`.
+const WEAK_BOARD_VALUE: i32 = -2000;
+/// Board values at or above this count as ideal for `strength`. An empty board with default
+/// weights scores about here.
+const STRONG_BOARD_VALUE: i32 = 0;
+
+/// Maps a board value onto 0 to 1, linearly between `WEAK_BOARD_VALUE` and `STRONG_BOARD_VALUE`.
+pub(crate) fn strength(value: i32) -> f64 {
+    let range = (STRONG_BOARD_VALUE - WEAK_BOARD_VALUE) as f64;
+    ((value - WEAK_BOARD_VALUE) as f64 / range).max(0.0).min(1.0)
+}
+
+/// Whether two placements leave the same cells filled with the same piece.
+pub(crate) fn same_placement(a: &FallingPiece, b: &FallingPiece) -> bool {
+    let mut a_cells = a.cells();
+    let mut b_cells = b.cells();
+    a_cells.sort();
+    b_cells.sort();
+    a.kind.0 == b.kind.0 && a_cells == b_cells
+}
+
+/// Whether `board` has too few pieces for the bot to pick a move: it needs the current piece, and
+/// with hold and an empty hold slot, another it could swap it for.
+pub(crate) fn queue_too_short(board: &Board, use_hold: bool) -> bool {
+    let needed = match use_hold && board.hold_piece.is_none() {
+        true => 2,
+        false => 1
+    };
+    board.next_queue().count() < needed
+}
+
+/// Lists every placement of the current piece, and of the held piece if hold is enabled.
+pub(crate) fn outcomes(board: &Board, options: &cold_clear::Options) -> Vec<Outcome> {
+    let mut queue = board.next_queue();
+    let current = match queue.next() {
+        Some(piece) => piece,
+        None => return vec![]
+    };
+    let mut choices = vec![(current, false)];
+    if options.use_hold {
+        if let Some(held) = board.hold_piece.or_else(|| queue.next()) {
+            if held != current {
+                choices.push((held, true));
+            }
+        }
+    }
+
+    let mut outcomes = vec![];
+    for (piece, hold) in choices {
+        let spawned = match options.spawn_rule.spawn(piece, board) {
+            Some(spawned) => spawned,
+            None => continue
+        };
+        for placement in find_moves(board, spawned, options.mode) {
+            let mut after = board.clone();
+            let next = after.advance_queue().unwrap();
+            if hold && after.hold(next).is_none() {
+                after.advance_queue();
+            }
+            let lock = after.lock_piece(placement.location);
+            outcomes.push(Outcome { placement, piece, hold, lock, board: after });
+        }
+    }
+    outcomes
+}
+
+/// Lists every outcome with its depth-1 evaluation, best first.
+pub(crate) fn candidates(
+    board: &Board,
+    options: &cold_clear::Options,
+    evaluator: &Standard
+) -> Vec<Candidate> {
+    let mut candidates: Vec<_> = outcomes(board, options)
+        .into_iter()
+        .map(|outcome| {
+            let eval = score(
+                evaluator,
+                &outcome.lock,
+                &outcome.board,
+                outcome.placement.inputs.time,
+                outcome.piece
+            );
+            Candidate { outcome, eval }
+        })
+        .collect();
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.eval));
+    candidates
+}
+
+#[derive(Serialize)]
+pub(crate) struct PieceValue {
+    pub(crate) piece: Piece,
+    /// The depth-1 evaluation of the best placement of the piece, or `None` if every placement
+    /// tops out.
+    pub(crate) eval: Option<i32>
+}
+
+/// Rates every piece by how well it could be placed on `board`'s field right now, best first.
+///
+/// Each piece is tried as the only piece in the queue, keeping the hold piece and counters. Only
+/// placements of the piece itself count, not those of the hold piece swapped in for it.
+pub(crate) fn piece_values(
+    board: &Board,
+    options: &cold_clear::Options,
+    evaluator: &Standard
+) -> Vec<PieceValue> {
+    let mut values: Vec<_> = crate::encoding::PIECES.iter()
+        .map(|&piece| {
+            let mut hypothetical = Board::new();
+            hypothetical.set_field(board.get_field());
+            hypothetical.hold_piece = board.hold_piece;
+            hypothetical.b2b_bonus = board.b2b_bonus;
+            hypothetical.combo = board.combo;
+            hypothetical.add_next_piece(piece);
+            let eval = candidates(&hypothetical, options, evaluator)
+                .into_iter()
+                .find(|candidate| !candidate.outcome.hold && !candidate.outcome.lock.locked_out)
+                .map(|candidate| candidate.eval);
+            PieceValue { piece, eval }
+        })
+        .collect();
+    values.sort_by_key(|value| std::cmp::Reverse(value.eval));
+    values
+}
+
+/// A coarse explanation of why the bot did or didn't use hold.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum HoldReason {
+    /// The bot didn't hold.
+    None,
+    /// Every placement of the current piece tops out.
+    AvoidDeath,
+    /// The held piece has a better immediate placement than the current piece.
+    BetterPiece,
+    /// The current piece has a better immediate placement, so holding pays off later in the plan.
+    Setup
+}
+
+/// Classifies a hold decision by comparing the best depth-1 evaluations with and without hold.
+pub(crate) fn hold_reason(
+    board: &Board,
+    options: &cold_clear::Options,
+    evaluator: &Standard,
+    held: bool
+) -> HoldReason {
+    if !held {
+        return HoldReason::None;
+    }
+    let candidates = candidates(board, options, evaluator);
+    let best = |hold: bool| candidates.iter()
+        .find(|c| c.outcome.hold == hold && !c.outcome.lock.locked_out)
+        .map(|c| c.eval);
+    match (best(false), best(true)) {
+        (None, _) => HoldReason::AvoidDeath,
+        (Some(without), Some(with)) if with >= without => HoldReason::BetterPiece,
+        _ => HoldReason::Setup
+    }
+}
+
+/// The best placement with and without holding, to compare the two.
+#[derive(Serialize)]
+pub(crate) struct HoldComparison {
+    pub(crate) play_now: Option<ScoredPlacement>,
+    /// Always `None` if hold is disabled.
+    pub(crate) hold: Option<ScoredPlacement>
+}
+
+pub(crate) fn hold_comparison(
+    board: &Board,
+    options: &cold_clear::Options,
+    evaluator: &Standard
+) -> HoldComparison {
+    let candidates = candidates(board, options, evaluator);
+    let best = |hold: bool| candidates.iter()
+        .find(|c| c.outcome.hold == hold)
+        .map(ScoredPlacement::new);
+    HoldComparison { play_now: best(false), hold: best(true) }
+}
+
+/// A promise that resolves after `ms` milliseconds.
+fn timer(ms: f64) -> js_sys::Promise {
+    js_sys::Promise::new(&mut |resolve, _| {
+        let set_timeout: js_sys::Function = js_sys::Reflect::get(&js_sys::global(), &"setTimeout".into())
+            .unwrap()
+            .dyn_into()
+            .unwrap();
+        set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(ms)).unwrap();
+    })
+}
+
+/// Lets the browser handle other events, so that long searches don't freeze the page.
+pub(crate) async fn yield_to_event_loop() {
+    wasm_bindgen_futures::JsFuture::from(timer(0.0)).await.unwrap();
+}
+
+/// A future that gives up on another once a timer fires; see `with_timeout`.
+struct Timeout<F> {
+    future: Pin<Box<F>>,
+    timer: wasm_bindgen_futures::JsFuture
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        match Pin::new(&mut self.timer).poll(cx) {
+            Poll::Ready(_) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending
+        }
+    }
+}
+
+/// Runs `future` for at most `ms` milliseconds, or returns `None` if it takes longer. The future
+/// is dropped then, along with any cold_clear interface it holds, which stops its worker.
+pub(crate) async fn with_timeout<F: Future>(ms: f64, future: F) -> Option<F::Output> {
+    Timeout { future: Box::pin(future), timer: timer(ms).into() }.await
+}
+
+/// How many placements the searches in this module try between yields to the event loop.
+const YIELD_INTERVAL: u32 = 1000;
+
+/// Whether a perfect clear could still be reached from `board` within `placements` pieces.
+///
+/// Every row up to the tallest column has to be cleared, and each piece fills 4 cells, so the
+/// empty cells below the top of the stack (or the row above it) must fit in the pieces left.
+fn perfect_clear_possible(board: &Board, placements: u32) -> bool {
+    let height = *board.column_heights().iter().max().unwrap();
+    let mut filled = 0;
+    for y in 0..height {
+        for x in 0..10 {
+            if board.occupied(x, y) {
+                filled += 1;
+            }
+        }
+    }
+    (height..height + 2).any(|rows| {
+        let empty = 10 * rows - filled;
+        empty % 4 == 0 && empty <= 4 * placements as i32
+    })
+}
+
+/// How many placements `perfect_clear` tries before giving up.
+const PERFECT_CLEAR_BUDGET: u32 = 200_000;
+
+/// Searches for a sequence of at most `max_placements` placements of the known pieces that ends
+/// in a perfect clear, giving up after `PERFECT_CLEAR_BUDGET` placements.
+///
+/// Boards already searched from are skipped. Their `position_key` includes the queue and hold
+/// piece, which only match if the same number of pieces was placed, so a board that led nowhere
+/// the first time can't lead anywhere from another path either.
+pub(crate) async fn perfect_clear(
+    board: &Board,
+    options: &cold_clear::Options,
+    max_placements: u32
+) -> Option<Vec<Step>> {
+    if max_placements == 0 {
+        return None;
+    }
+    let mut path: Vec<Step> = vec![];
+    let mut stack = vec![outcomes(board, options)];
+    let mut searched = std::collections::HashSet::new();
+    let mut tried = 0;
+    while let Some(frame) = stack.last_mut() {
+        let outcome = match frame.pop() {
+            Some(outcome) => outcome,
+            None => {
+                stack.pop();
+                path.pop();
+                continue;
+            }
+        };
+        tried += 1;
+        if tried > PERFECT_CLEAR_BUDGET {
+            return None;
+        }
+        if tried % YIELD_INTERVAL == 0 {
+            yield_to_event_loop().await;
+        }
+        if outcome.lock.locked_out {
+            continue;
+        }
+        if outcome.lock.perfect_clear {
+            path.push(Step::new(&outcome));
+            return Some(path);
+        }
+        let placements_left = max_placements - path.len() as u32 - 1;
+        if placements_left > 0
+            && perfect_clear_possible(&outcome.board, placements_left)
+            && searched.insert(crate::encoding::position_key(&outcome.board))
+        {
+            path.push(Step::new(&outcome));
+            stack.push(outcomes(&outcome.board, options));
+        }
+    }
+    None
+}
+
+/// Whether `queue` could have been dealt by a 7-bag randomizer, with the first bag boundary
+/// anywhere in its first 7 pieces.
+fn bag_legal(queue: &[Piece]) -> bool {
+    (0..7).any(|offset| {
+        let (head, rest) = queue.split_at(offset.min(queue.len()));
+        std::iter::once(head)
+            .chain(rest.chunks(7))
+            .all(|bag| bag.iter().enumerate().all(|(i, piece)| !bag[..i].contains(piece)))
+    })
+}
+
+/// Checks the invariants of `board` that don't depend on cold_clear, describing each one that
+/// doesn't hold.
+///
+/// `seven_bag` is whether the queue is expected to come from a 7-bag randomizer.
+pub(crate) fn integrity_violations(
+    board: &Board,
+    options: &cold_clear::Options,
+    seven_bag: bool
+) -> Vec<String> {
+    let mut violations = vec![];
+    let queue: Vec<_> = board.next_queue().collect();
+    if seven_bag && !bag_legal(&queue) {
+        violations.push(format!("the queue {:?} can't come from a 7-bag", queue));
+    }
+    if !options.use_hold && board.hold_piece.is_some() {
+        violations.push(format!("hold is disabled but holds {:?}", board.hold_piece.unwrap()));
+    }
+    for (y, row) in board.get_field().iter().enumerate() {
+        if row.iter().all(|&filled| filled) {
+            violations.push(format!("row {} is full but wasn't cleared", y));
+        }
+    }
+    violations
+}
+
+/// A condition on the board, checked after every placement by `set_breakpoint`.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Breakpoint {
+    /// Met once any column is at least this tall.
+    #[serde(default)]
+    height: Option<i32>,
+    /// Met once there are at least this many empty cells under the tops of their columns.
+    #[serde(default)]
+    holes: Option<u32>
+}
+
+impl Breakpoint {
+    pub(crate) fn matches(&self, board: &Board) -> bool {
+        let heights = board.column_heights();
+        let height = self.height.map_or(false, |height| heights.iter().any(|&h| h >= height));
+        let holes = self.holes.map_or(false, |holes| {
+            let count = (0..10)
+                .map(|x| (0..heights[x]).filter(|&y| !board.occupied(x as i32, y)).count())
+                .sum::<usize>();
+            count as u32 >= holes
+        });
+        height || holes
+    }
+}
+
+/// An empty cell under the top of its column.
+#[derive(Serialize)]
+pub(crate) struct Hole {
+    pub(crate) row: i32,
+    /// How many filled cells of the column are above the hole.
+    pub(crate) covered_by: u32
+}
+
+/// Lists the holes of each column from the bottom up, with rows counted from the bottom.
+pub(crate) fn holes(board: &Board) -> Vec<Vec<Hole>> {
+    let heights = board.column_heights();
+    (0..10)
+        .map(|x| {
+            let mut holes = vec![];
+            let mut covered_by = 0;
+            for y in (0..heights[x]).rev() {
+                match board.occupied(x as i32, y) {
+                    true => covered_by += 1,
+                    false => holes.push(Hole { row: y, covered_by })
+                }
+            }
+            holes.reverse();
+            holes
+        })
+        .collect()
+}
+
+/// Finds the column the stack is built around the same way the standard evaluator does: the
+/// lowest column, provided every other column is filled on at least the row right above it.
+pub(crate) fn well_column(board: &Board) -> Option<usize> {
+    let heights = board.column_heights();
+    let mut well = 0;
+    for x in 1..10 {
+        if heights[x] <= heights[well] {
+            well = x;
+        }
+    }
+    let covered = (0..10)
+        .filter(|&x| x != well)
+        .all(|x| board.occupied(x as i32, heights[well]));
+    if covered {
+        Some(well)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::{EMPTY_CELL, GARBAGE_CELL};
+
+    fn piece(piece: Piece, rotation: RotationState, x: i32, y: i32) -> FallingPiece {
+        FallingPiece { kind: PieceState(piece, rotation), x, y, tspin: TspinStatus::None }
+    }
+
+    /// An empty board raised by two rows of garbage, the top one with its hole in column 0 and the
+    /// bottom one in column 3.
+    fn garbage_board() -> Board {
+        let mut board = Board::new();
+        assert!(add_garbage(&mut board, &[0, 3]));
+        board
+    }
+
+    #[test]
+    fn garbage_rises_from_the_bottom() {
+        let board = garbage_board();
+        assert!(!board.occupied(3, 0));
+        assert!(board.occupied(0, 0));
+        assert!(!board.occupied(0, 1));
+        assert!(board.occupied(3, 1));
+        assert!(!board.occupied(5, 2));
+    }
+
+    #[test]
+    fn garbage_can_top_out() {
+        let mut board = Board::new();
+        let mut field = [[false; 10]; 40];
+        field[39][0] = true;
+        board.set_field(field);
+        assert!(!add_garbage(&mut board, &[0]));
+    }
+
+    #[test]
+    fn holes_are_counted_under_their_column() {
+        let holes = holes(&garbage_board());
+        assert!(holes[0].is_empty());
+        assert_eq!(holes[3].len(), 1);
+        assert_eq!(holes[3][0].row, 0);
+        assert_eq!(holes[3][0].covered_by, 1);
+        assert!(holes[5].is_empty());
+    }
+
+    #[test]
+    fn the_garbage_hole_is_the_well() {
+        assert_eq!(well_column(&garbage_board()), Some(0));
+        assert_eq!(well_column(&Board::new()), None);
+    }
+
+    #[test]
+    fn same_placement_compares_cells() {
+        let t = piece(Piece::T, RotationState::North, 4, 0);
+        assert!(same_placement(&t, &t));
+        assert!(!same_placement(&t, &piece(Piece::T, RotationState::North, 5, 0)));
+        assert!(!same_placement(&t, &piece(Piece::T, RotationState::South, 4, 0)));
+    }
+
+    #[test]
+    fn a_held_piece_can_stand_in_for_the_second_piece() {
+        let mut board = Board::new();
+        board.add_next_piece(Piece::T);
+        assert!(queue_too_short(&board, true));
+        assert!(!queue_too_short(&board, false));
+        board.hold_piece = Some(Piece::I);
+        assert!(!queue_too_short(&board, true));
+        assert!(queue_too_short(&Board::new(), false));
+    }
+
+    #[test]
+    fn strength_is_clamped() {
+        assert_eq!(strength(WEAK_BOARD_VALUE - 1), 0.0);
+        assert_eq!(strength(STRONG_BOARD_VALUE + 1), 1.0);
+        assert_eq!(strength((WEAK_BOARD_VALUE + STRONG_BOARD_VALUE) / 2), 0.5);
+    }
+
+    #[test]
+    fn downstacking_needs_the_garbage_gone() {
+        let mut cells = [[EMPTY_CELL; 10]; 40];
+        cells[0] = [GARBAGE_CELL; 10];
+        cells[0][3] = EMPTY_CELL;
+        cells[1] = [GARBAGE_CELL; 10];
+        cells[1][0] = EMPTY_CELL;
+        assert_eq!(downstack_length(&garbage_board(), &cells, &[]), None);
+        assert_eq!(downstack_length(&Board::new(), &[[EMPTY_CELL; 10]; 40], &[]), Some(0));
+    }
+}
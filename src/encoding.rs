@@ -0,0 +1,183 @@
+//! A compact binary encoding of moves, for sending them over the network.
+//!
+//! A move is packed into the low 10 bits of a little-endian `u16`:
+//!
+//! | Bits | Field                                               |
+//! |------|-----------------------------------------------------|
+//! | 0-2  | Piece, in the order I, O, T, L, J, S, Z             |
+//! | 3-4  | Rotation, in the order North, East, South, West     |
+//! | 5-8  | Column of the piece's center, 0 to 9 from the left  |
+//! | 9    | Whether the move uses hold                          |
+//!
+//! The row isn't encoded; the receiving end is expected to drop the piece on its own board.
+//!
+//! Positions are encoded as strings instead, to be used as keys; see `position_key`.
+
+use libtetris::*;
+use serde::Serialize;
+
+pub(crate) const PIECES: [Piece; 7] = [Piece::I, Piece::O, Piece::T, Piece::L, Piece::J, Piece::S, Piece::Z];
+pub(crate) const ROTATIONS: [RotationState; 4] = [
+    RotationState::North,
+    RotationState::East,
+    RotationState::South,
+    RotationState::West
+];
+
+#[derive(Serialize)]
+pub(crate) struct CompactMove {
+    pub(crate) piece: Piece,
+    pub(crate) rotation: RotationState,
+    pub(crate) x: i32,
+    pub(crate) hold: bool
+}
+
+pub(crate) fn encode(mv: &cold_clear::Move) -> Result<[u8; 2], String> {
+    let location = mv.expected_location;
+    if !(0..10).contains(&location.x) {
+        return Err(format!("column must be between 0 and 9 (got {})", location.x));
+    }
+    let piece = PIECES.iter().position(|&p| p == location.kind.0).unwrap() as u16;
+    let rotation = ROTATIONS.iter().position(|&r| r == location.kind.1).unwrap() as u16;
+    let packed = piece | rotation << 3 | (location.x as u16) << 5 | (mv.hold as u16) << 9;
+    Ok(packed.to_le_bytes())
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<CompactMove, String> {
+    if bytes.len() != 2 {
+        return Err(format!("an encoded move must be 2 bytes (got {})", bytes.len()));
+    }
+    let packed = u16::from_le_bytes([bytes[0], bytes[1]]);
+    let piece = PIECES.get((packed & 0b111) as usize)
+        .ok_or_else(|| format!("invalid piece index {}", packed & 0b111))?;
+    let x = (packed >> 5 & 0b1111) as i32;
+    if x >= 10 {
+        return Err(format!("column must be between 0 and 9 (got {})", x));
+    }
+    Ok(CompactMove {
+        piece: *piece,
+        rotation: ROTATIONS[(packed >> 3 & 0b11) as usize],
+        x,
+        hold: packed >> 9 & 1 != 0
+    })
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes `bytes` as URL-safe base64, without padding.
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 4 + 2) / 3);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = (group[0] as u32) << 16 | (group[1] as u32) << 8 | group[2] as u32;
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64[(bits >> (18 - 6 * i) & 0b11_1111) as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// Encodes everything that affects the bot's decisions into a string, so that two boards get
+/// the same key exactly when their field, hold, queue and counters are the same.
+///
+/// Before base64, the key is laid out as:
+/// - a byte with back-to-back in bit 0 and the hold piece's index plus one (or 0) in bits 1-3
+/// - the combo, as a little-endian `u32`
+/// - the length of the queue, then a byte per queued piece with its index
+/// - the number of rows up to the highest filled cell, then those rows from the bottom, 10 bits
+///   each, packed starting from the low bits of each byte
+pub(crate) fn position_key(board: &Board) -> String {
+    let index = |piece| PIECES.iter().position(|&p| p == piece).unwrap() as u8;
+    let mut bytes = vec![];
+    let hold = board.hold_piece.map_or(0, |piece| index(piece) + 1);
+    bytes.push(board.b2b_bonus as u8 | hold << 1);
+    bytes.extend_from_slice(&board.combo.to_le_bytes());
+    let queue: Vec<_> = board.next_queue().collect();
+    bytes.push(queue.len() as u8);
+    bytes.extend(queue.into_iter().map(index));
+
+    let height = *board.column_heights().iter().max().unwrap();
+    bytes.push(height as u8);
+    let mut bits = 0;
+    let mut pending = 0u32;
+    for row in &board.get_field()[..height as usize] {
+        for &filled in row {
+            pending |= (filled as u32) << bits;
+            bits += 1;
+            if bits == 8 {
+                bytes.push(pending as u8);
+                pending = 0;
+                bits = 0;
+            }
+        }
+    }
+    if bits > 0 {
+        bytes.push(pending as u8);
+    }
+    base64(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placement(piece: Piece, rotation: RotationState, x: i32) -> FallingPiece {
+        FallingPiece { kind: PieceState(piece, rotation), x, y: 20, tspin: TspinStatus::None }
+    }
+
+    #[test]
+    fn moves_round_trip() {
+        for &piece in &PIECES {
+            for &rotation in &ROTATIONS {
+                for x in 0..10 {
+                    for &hold in &[false, true] {
+                        let mv = cold_clear::Move {
+                            inputs: Default::default(),
+                            expected_location: placement(piece, rotation, x),
+                            hold
+                        };
+                        let decoded = decode(&encode(&mv).unwrap()).unwrap();
+                        assert_eq!(decoded.piece, piece);
+                        assert_eq!(decoded.rotation, rotation);
+                        assert_eq!(decoded.x, x);
+                        assert_eq!(decoded.hold, hold);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bad_moves_are_rejected() {
+        let mv = cold_clear::Move {
+            inputs: Default::default(),
+            expected_location: placement(Piece::T, RotationState::North, 10),
+            hold: false
+        };
+        assert!(encode(&mv).is_err());
+        assert!(decode(&[0]).is_err());
+        // Piece index 7 doesn't exist.
+        assert!(decode(&[0b111, 0]).is_err());
+        // Column 10 doesn't either.
+        assert!(decode(&(10u16 << 5).to_le_bytes()).is_err());
+    }
+
+    #[test]
+    fn position_keys_tell_boards_apart() {
+        let mut board = Board::new();
+        board.add_next_piece(Piece::T);
+        board.add_next_piece(Piece::I);
+        assert_eq!(position_key(&board), position_key(&board.clone()));
+
+        let mut held = board.clone();
+        held.hold_piece = Some(Piece::O);
+        assert_ne!(position_key(&board), position_key(&held));
+
+        let mut filled = board.clone();
+        let mut field = [[false; 10]; 40];
+        field[0][3] = true;
+        filled.set_field(field);
+        assert_ne!(position_key(&board), position_key(&filled));
+    }
+}
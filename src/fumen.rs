@@ -0,0 +1,186 @@
+//! Encoding of positions as fumen strings, the format of the fumen.zui.jp editor and the tools
+//! built around it.
+//!
+//! Only what a one-page quiz needs is supported: a field, the piece placed on it, and a comment.
+
+use libtetris::*;
+
+use crate::encoding::PIECES;
+use crate::worker::{EMPTY_CELL, GARBAGE_CELL};
+
+const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The rows fumen has above the floor. Filled cells above them are left out.
+const ROWS: usize = 23;
+/// The cells of a fumen field: the rows above the floor, plus a row of garbage below it.
+const FIELD_CELLS: u32 = (ROWS as u32 + 1) * 10;
+
+/// Appends `value` as `digits` base64 digits, least significant first.
+fn push(data: &mut String, mut value: u32, digits: u32) {
+    for _ in 0..digits {
+        data.push(TABLE[(value % 64) as usize] as char);
+        value /= 64;
+    }
+}
+
+fn block(piece: Piece) -> u32 {
+    match piece {
+        Piece::I => 1,
+        Piece::L => 2,
+        Piece::O => 3,
+        Piece::Z => 4,
+        Piece::T => 5,
+        Piece::J => 6,
+        Piece::S => 7
+    }
+}
+
+fn cell_block(cell: u8) -> u32 {
+    match cell {
+        EMPTY_CELL => 0,
+        GARBAGE_CELL => 8,
+        kind => block(PIECES[kind as usize - 1])
+    }
+}
+
+fn rotation(rotation: RotationState) -> u32 {
+    match rotation {
+        RotationState::South => 0,
+        RotationState::East => 1,
+        RotationState::North => 2,
+        RotationState::West => 3
+    }
+}
+
+/// The cell fumen stores `piece` at, counting from the top left.
+///
+/// fumen rotates pieces around the same cells as libtetris, but stores a few rotations of I, O, S
+/// and Z by another cell.
+fn position(piece: &FallingPiece) -> u32 {
+    let PieceState(kind, rotation) = piece.kind;
+    let (dx, dy) = match (kind, rotation) {
+        (Piece::O, RotationState::North) => (0, 1),
+        (Piece::O, RotationState::South) => (-1, 0),
+        (Piece::O, RotationState::West) => (-1, 1),
+        (Piece::I, RotationState::South) => (-1, 0),
+        (Piece::I, RotationState::West) => (0, 1),
+        (Piece::S, RotationState::North) => (0, 1),
+        (Piece::S, RotationState::East) => (-1, 0),
+        (Piece::Z, RotationState::North) => (0, 1),
+        (Piece::Z, RotationState::West) => (1, 0),
+        _ => (0, 0)
+    };
+    ((ROWS as i32 - 1 - (piece.y + dy)) * 10 + piece.x + dx) as u32
+}
+
+/// Stores the field as its difference from the previous page, which is empty here.
+fn push_field(data: &mut String, cells: &[[u8; 10]; 40]) {
+    // Cells go from the top row down, ending with the garbage row, which is left empty. Each
+    // difference is offset by 8, and runs of the same difference are stored together.
+    let differences = (0..ROWS).rev()
+        .flat_map(move |y| cells[y].iter().map(|&cell| cell_block(cell)))
+        .chain(std::iter::repeat(0).take(10))
+        .map(|block| block + 8);
+    let mut runs: Vec<(u32, u32)> = vec![];
+    for difference in differences {
+        match runs.last_mut() {
+            Some((last, length)) if *last == difference => *length += 1,
+            _ => runs.push((difference, 1))
+        }
+    }
+    for &(difference, length) in &runs {
+        push(data, difference * FIELD_CELLS + length - 1, 2);
+    }
+    if runs.len() == 1 && runs[0].0 == 8 {
+        // An unchanged field is followed by how many of the next pages leave it unchanged too.
+        push(data, 0, 1);
+    }
+}
+
+/// Stores `comment` the way JavaScript's `escape` encodes it, 4 characters at a time.
+fn push_comment(data: &mut String, comment: &str) {
+    let escaped: String = comment.chars()
+        .map(|c| match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '@' | '*' | '_' | '+' | '-' | '.' | '/' => {
+                c.to_string()
+            }
+            _ => format!("%{:02X}", c as u32)
+        })
+        .collect();
+    push(data, escaped.len() as u32, 2);
+    for chunk in escaped.as_bytes().chunks(4) {
+        let value = chunk.iter().rev().fold(0, |value, &c| value * 96 + (c - b' ') as u32);
+        push(data, value, 5);
+    }
+}
+
+/// Encodes a one-page quiz of `board`, whose cells are filled as `cells` says, with `solution`
+/// placed on it, or returns `None` if `solution` is above the rows fumen has.
+///
+/// The page's comment is the quiz's queue, `#Q=[hold](current)next`, so that fumen checks the
+/// solution against it.
+pub(crate) fn quiz(
+    board: &Board,
+    cells: &[[u8; 10]; 40],
+    solution: &FallingPiece
+) -> Option<String> {
+    if solution.cells().iter().any(|&(_, y)| y >= ROWS as i32) {
+        return None;
+    }
+    let mut queue = board.next_queue();
+    let current = queue.next()?;
+    let hold = board.hold_piece.map_or(String::new(), |piece| format!("{:?}", piece));
+    let next: String = queue.map(|piece| format!("{:?}", piece)).collect();
+    let comment = format!("#Q=[{}]({:?}){}", hold, current, next);
+
+    let mut data = String::new();
+    push_field(&mut data, cells);
+    // The flags, from the lowest bit, are rising garbage, mirroring, guideline colors, a comment,
+    // and not locking the piece. Only guideline colors and the comment are set, so the piece locks.
+    let flags = 0b01100;
+    let PieceState(kind, state) = solution.kind;
+    let location = position(solution) + FIELD_CELLS * flags;
+    let action = block(kind) + 8 * (rotation(state) + 4 * location);
+    push(&mut data, action, 3);
+    push_comment(&mut data, &comment);
+    Some(format!("v115@{}", data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t_piece(y: i32) -> FallingPiece {
+        FallingPiece {
+            kind: PieceState(Piece::T, RotationState::North),
+            x: 4,
+            y,
+            tspin: TspinStatus::None
+        }
+    }
+
+    #[test]
+    fn empty_field_quiz() {
+        let mut board = Board::new();
+        board.add_next_piece(Piece::T);
+        board.add_next_piece(Piece::I);
+        let quiz = quiz(&board, &[[EMPTY_CELL; 10]; 40], &t_piece(0)).unwrap();
+        // An empty field is a single run of unchanged cells, followed by no repeated pages.
+        assert!(quiz.starts_with("v115@vhA"));
+        // Checked by decoding it with tetris-fumen's rules rather than this encoder: a locked
+        // T in spawn orientation at x 4 on the bottom row, guideline colors, and `#Q=[](T)I`.
+        assert_eq!(quiz, "v115@vhAVQYVAFLDmClcJSAVDEHBEooRBUoAVBpAAAA");
+    }
+
+    #[test]
+    fn placements_above_fumen_are_rejected() {
+        let mut board = Board::new();
+        board.add_next_piece(Piece::T);
+        assert!(quiz(&board, &[[EMPTY_CELL; 10]; 40], &t_piece(ROWS as i32)).is_none());
+    }
+
+    #[test]
+    fn an_empty_queue_has_no_quiz() {
+        assert!(quiz(&Board::new(), &[[EMPTY_CELL; 10]; 40], &t_piece(0)).is_none());
+    }
+}
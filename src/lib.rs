@@ -1,8 +1,25 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::rc::Rc;
+
 use webutil::channel::{channel, Sender, oneshot, Oneshot};
 
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use libtetris::*;
+use serde::{Serialize, Deserialize};
+
+mod analysis;
+mod encoding;
+mod fumen;
+mod options;
+mod selfplay;
+mod tuning;
+mod worker;
+
+use options::{CoordinateOrigin, LaunchOptions};
+use worker::{InterfaceCommand, Worker, WorkerStatus, EMPTY_CELL, GARBAGE_CELL};
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
@@ -10,89 +27,333 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 #[wasm_bindgen]
 pub struct CCInterface {
-    send: Sender<InterfaceCommand>
+    send: Sender<InterfaceCommand>,
+    status: Rc<WorkerStatus>,
+    pending: Rc<RefCell<PendingPromises>>,
+    piece_set: Option<Vec<Piece>>,
+    standing_incoming: Cell<u32>,
+    origin: CoordinateOrigin
+}
+
+/// The reject functions of promises that haven't settled yet, so that they can be rejected if the
+/// interface is dropped before the worker gets to them.
+#[derive(Default)]
+struct PendingPromises {
+    next_id: u32,
+    rejects: HashMap<u32, js_sys::Function>
+}
+
+impl PendingPromises {
+    fn insert(&mut self, reject: js_sys::Function) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.rejects.insert(id, reject);
+        id
+    }
+}
+
+/// Why the bot could not continue playing.
+#[derive(Serialize, Clone, Copy, Debug)]
+enum DeathReason {
+    /// The board got too tall for the bot to find any placement.
+    #[serde(rename = "topout")]
+    TopOut,
+    /// The next piece overlaps existing blocks at its spawn position.
+    #[serde(rename = "block_out")]
+    BlockOut
 }
 
 #[derive(Debug)]
 struct ArgumentError<T>(T);
 
-enum InterfaceCommand {
-    Reset {
-        field: [[bool; 10]; 40],
-        b2b: bool,
-        combo: u32
-    },
-    NewPiece(Piece),
-    NextMove(u32, Oneshot<Option<(cold_clear::Move, cold_clear::Info)>>),
-    ForceAnalysisLine(Vec<FallingPiece>)
+#[derive(Debug)]
+struct WorkerError {
+    code: &'static str
+}
+
+#[derive(Debug)]
+struct PieceError {
+    code: &'static str,
+    valid: &'static [Piece]
+}
+
+#[derive(Serialize)]
+struct PieceInfo {
+    piece: Piece,
+    /// The piece's index in libtetris's piece ordering (I, O, T, L, J, S, Z).
+    color: u8,
+    rotations: Vec<RotationInfo>
+}
+
+#[derive(Serialize)]
+struct RotationInfo {
+    rotation: RotationState,
+    /// Cell offsets from the piece's center as `[x, y]` pairs, with y pointing up.
+    cells: [(i32, i32); 4]
+}
+
+#[derive(Serialize)]
+struct NextTwo {
+    #[serde(rename = "move")]
+    mv: cold_clear::Move,
+    info: cold_clear::Info,
+    /// The cells the move's piece will occupy once placed.
+    cells: [(i32, i32); 4],
+    follow_up: Option<PlannedPlacement>
+}
+
+#[derive(Serialize)]
+struct PlannedPlacement {
+    location: FallingPiece,
+    cells: [(i32, i32); 4]
+}
+
+impl PlannedPlacement {
+    fn new(location: FallingPiece, origin: CoordinateOrigin) -> Self {
+        Self { location, cells: origin.cells(location.cells()) }
+    }
 }
 
-enum WorkerState {
-    Initializing(Board, u32),
-    Ready(cold_clear::Interface)
+#[derive(Serialize)]
+struct DownstackPlan {
+    placements: Vec<PlannedPlacement>,
+    pieces: u32
+}
+
+/// The placements the bot intends to make, starting with the move it just chose.
+fn plan(info: &cold_clear::Info) -> &[(FallingPiece, LockResult)] {
+    match info {
+        cold_clear::Info::Normal(info) => &info.plan,
+        cold_clear::Info::PcLoop(info) => &info.plan
+    }
+}
+
+/// Back-to-back status as provided by the client: either a flag or a chain level.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BackToBack {
+    Active(bool),
+    Level(u32)
+}
+
+/// A cell of a field provided by the client: either whether it is filled, or what fills it.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FieldCell {
+    Filled(bool),
+    Kind(u8)
+}
+
+/// Parses a field of 40 rows of 10 cells into what fills each cell, with bools counting as
+/// garbage.
+fn parse_field(field: JsValue) -> Result<[[u8; 10]; 40], JsValue> {
+    let src_field: Vec<[FieldCell; 10]> = field
+        .into_serde()
+        .map_err(to_js_error)?;
+    let mut cells = [[EMPTY_CELL; 10]; 40];
+    if src_field.len() != cells.len() {
+        let message = format!("`field` must be 40 rows (got {})", src_field.len());
+        return Err(to_js_error(ArgumentError(message)));
+    }
+    for (src, dest) in src_field.iter().zip(cells.iter_mut()) {
+        for (cell, kind) in src.iter().zip(dest.iter_mut()) {
+            *kind = match *cell {
+                FieldCell::Filled(true) => GARBAGE_CELL,
+                FieldCell::Filled(false) => EMPTY_CELL,
+                FieldCell::Kind(kind) if kind <= GARBAGE_CELL => kind,
+                FieldCell::Kind(kind) => {
+                    let message = format!("field cells must be 0 to 8 (got {})", kind);
+                    return Err(to_js_error(ArgumentError(message)));
+                }
+            };
+        }
+    }
+    Ok(cells)
+}
+
+fn parse_b2b(b2b: JsValue) -> Result<bool, JsValue> {
+    let b2b = b2b
+        .into_serde()
+        .map_err(to_js_error)?;
+    Ok(match b2b {
+        BackToBack::Active(active) => active,
+        BackToBack::Level(level) => level > 0
+    })
+}
+
+/// How many errors the error log remembers.
+const ERROR_LOG_LENGTH: usize = 64;
+
+#[derive(Serialize, Clone)]
+struct ErrorEntry {
+    /// When the error was raised, as a `Date.now()` timestamp.
+    time: f64,
+    name: &'static str,
+    message: String
+}
+
+thread_local! {
+    /// Every error the module has raised recently, across all interfaces, oldest first.
+    static ERROR_LOG: RefCell<VecDeque<ErrorEntry>> = RefCell::new(VecDeque::new());
 }
 
 fn to_js_error<E: std::fmt::Debug>(error: E) -> JsValue {
-    let js_error = js_sys::Error::new(&format!("{:?}", error));
-    js_error.set_name(std::any::type_name::<E>());
+    let name = std::any::type_name::<E>();
+    let message = format!("{:?}", error);
+    ERROR_LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        if log.len() == ERROR_LOG_LENGTH {
+            log.pop_front();
+        }
+        log.push_back(ErrorEntry { time: js_sys::Date::now(), name, message: message.clone() });
+    });
+    let js_error = js_sys::Error::new(&message);
+    js_error.set_name(name);
     js_error.dyn_into().unwrap()
 }
 
 #[wasm_bindgen]
 impl CCInterface {
     /// Launches a bot worker from the provided URI with the specified starting board and options.
-    pub fn launch(worker_uri: String, options: JsValue, evaluator: JsValue) -> Result<CCInterface, JsValue> {
+    /// 
+    /// Besides cold_clear's own options, `options` may contain a `piece_set` array listing the only
+    /// pieces the game's randomizer can produce. If it leaves out any piece, the bot stops assuming
+    /// a 7-bag: speculation and the evaluator's bag awareness are turned off, and `add_next_piece`
+    /// rejects pieces outside the set.
+    /// 
+    /// Setting `debug` to true in `options` makes the worker log every command it processes, which
+    /// can then be read with `debug_log`.
+    /// 
+    /// Setting `instant_gravity` to true is meant for 20G or instant-drop games. The bot then only
+    /// considers movement that works when pieces fall instantly, and soft drops are left out of
+    /// the `inputs` of its moves. The placements themselves are unaffected.
+    /// 
+    /// `spawn_row` and `spawn_rotation` override where pieces spawn, so that the `inputs` of the
+    /// bot's moves start from the right place. Only guideline spawn positions are supported: row 19
+    /// (counting from 0 at the bottom, falling back to 20 if 19 is blocked), or row 21 with an
+    /// immediate drop, and the `North` rotation. cold_clear can't spawn pieces on row 20 unless 19
+    /// is blocked, so a `spawn_row` of 20 is treated the same as 19. The inputs only differ from a
+    /// game spawning on row 20 once the stack gets near the spawn rows.
+    /// 
+    /// `max_height` restricts the bot to placements that leave every column at most that many rows
+    /// tall. If the move the bot picks would break the limit, the best placement within it according
+    /// to the evaluator is played instead, discarding previous computations. If there is none,
+    /// `next_move` resolves with `null`, but the bot hasn't died, as with `forbid_placements`.
+    /// 
+    /// Setting `allow_soft_drop` to false (it defaults to true) restricts the bot to placements it
+    /// can reach with shifts, rotations and a hard drop, without tucks or spins below the surface.
+    /// The bot's search only considers those, so if none of them survive, the best placement that
+    /// soft drops according to the evaluator is played instead, and the bot only dies if there is
+    /// none either. This has no effect with `instant_gravity`, where pieces are always on the
+    /// surface.
+    /// 
+    /// Setting `speed_mode` to true is meant for sprint-style modes where execution time matters
+    /// more than attack. The evaluator's `move_time` penalty is quadrupled, so the bot leans
+    /// towards placements needing fewer inputs and picks them when placements are otherwise close.
+    /// This is only a bias: soft drops and spins are still searched, unless `allow_soft_drop` is
+    /// false.
+    /// 
+    /// Setting `adaptive_nodes` to true lets the bot think harder under pressure: `min_nodes` and
+    /// `max_nodes` are doubled while 4 to 7 lines of garbage are incoming, and tripled at 8 lines or
+    /// more, going by the `incoming` passed to `next_move`. Only moving from one of these levels to
+    /// another relaunches the bot, which throws away previous computations.
+    /// 
+    /// Setting `defensive_under_pressure` to true makes the bot play safer under pressure, going by
+    /// the same levels of incoming garbage: the evaluator's `height`, `top_half`, `top_quarter` and
+    /// `jeopardy` penalties are doubled while 4 to 7 lines are incoming, and tripled at 8 lines or
+    /// more, on top of any `set_aggression` level. Attack weights are left as they are. As with
+    /// `adaptive_nodes`, only moving from one level to another relaunches the bot, and the two
+    /// options share a relaunch when they change together.
+    /// 
+    /// Setting `reward_b2b` or `reward_combo` to false (both default to true) zeroes the
+    /// evaluator's back-to-back weights (`back_to_back` and `b2b_clear`) or its `combo_garbage`
+    /// weight, for efficiency-oriented play where attack doesn't matter.
+    /// 
+    /// `preferred_wells` lists columns (0 to 9, from the left) the bot should try to keep its well
+    /// in. Each of them gets the best of the evaluator's `well_column` weights plus a bonus, so the
+    /// bot favors them over every other column. Any other column index throws an error.
+    /// 
+    /// `preview_count` is the number of next pieces the game shows. If the client passes the bot
+    /// pieces beyond those, such as the rest of a known bag, the bot still uses them, but move
+    /// summaries count plan placements past the preview as speculative. The search itself weighs
+    /// all known pieces the same, since cold_clear has no notion of confidence.
+    /// 
+    /// `max_queue_consider` caps how many pieces of the queue, counting the current piece, the bot
+    /// is given. Pieces passed to `add_next_piece` beyond that are held back by the worker and
+    /// given to the bot one by one as it places pieces, so its search never looks further ahead.
+    /// Everything that reads the bot's queue, such as `on_commit` records, only sees the pieces it
+    /// has been given. With speculation, the bot speculates on the pieces past the cap the same way
+    /// it does past the end of the queue, so a cap saves memory but makes the search less certain.
+    /// The cap must be at least the 2 pieces the bot waits for before it starts thinking (3 with
+    /// hold), unless `immediate_start` is set, in which case it must be at least 1 (2 with hold).
+    /// 
+    /// `coordinate_origin` sets the convention for the fields passed to `reset` and `new_game` and
+    /// the `cells` the bot provides. It defaults to `"bottom_left"`, where row 0 is the bottom row
+    /// and y points up. With `"top_left"`, row 0 is the top row of the 40-row field and y points
+    /// down. Piece locations such as a move's `expected_location` always use the bottom-left
+    /// convention, since they describe the piece's center rather than cells.
+    /// 
+    /// `commit_plan` lets the bot play several placements of a plan per search, for games where
+    /// move latency matters more than reacting to each new piece. After a `next_move` that
+    /// searched, the next `commit_plan - 1` placements of the plan it returned are kept (0 and 1
+    /// both search for every move). Each later `next_move` plays the next kept placement right
+    /// away, with the info of the move the plan came from, as long as it is a placement of the
+    /// current or held piece on the bot's board and respects `max_height`. If a piece that doesn't
+    /// match the plan has been fed, or the board has since been reset, the rest of the plan is
+    /// thrown away and the bot searches as usual. The bot is told about the placement afterwards,
+    /// and relaunched if it would have played something else, which throws away previous
+    /// computations.
+    /// 
+    /// If provided, `on_ready` is called once the bot has received enough pieces to start thinking,
+    /// after which it can be asked for moves.
+    /// 
+    /// Setting `immediate_start` to true makes the bot ready as soon as it is launched, instead of
+    /// after its first 2 pieces (3 with hold), so `on_ready` is called right away. While the bot
+    /// has no piece to place, or only one with hold and nothing held, `next_move` resolves with
+    /// `null` without the bot dying, and the bot starts thinking once pieces are added with
+    /// `add_next_piece`.
+    pub fn launch(
+        worker_uri: String,
+        options: JsValue,
+        evaluator: JsValue,
+        on_ready: Option<js_sys::Function>
+    ) -> Result<CCInterface, JsValue> {
         #[cfg(feature = "console_error_panic_hook")]
         console_error_panic_hook::set_once();
-        let options: cold_clear::Options = options
+        let mut options: LaunchOptions = options
             .into_serde()
             .map_err(to_js_error)?;
         let evaluator: cold_clear::evaluation::Standard = evaluator
             .into_serde()
             .map_err(to_js_error)?;
-        let mut interface_args = Some((options, evaluator));
+        options.apply_spawn_overrides()
+            .map_err(|message| to_js_error(ArgumentError(message)))?;
+        options.check_preferred_wells()
+            .map_err(|message| to_js_error(ArgumentError(message)))?;
+        options.check_max_queue_consider()
+            .map_err(|message| to_js_error(ArgumentError(message)))?;
+        if options.restricted_pieces() {
+            options.cold_clear.speculate = false;
+        }
+        if options.instant_gravity {
+            options.cold_clear.mode = MovementMode::TwentyG;
+        } else if !options.allow_soft_drop {
+            options.cold_clear.mode = MovementMode::HardDropOnly;
+        }
+        let piece_set = options.piece_set.clone();
+        let origin = options.coordinate_origin;
         let (send, recv) = channel();
-        wasm_bindgen_futures::spawn_local(async move {
-            let mut state = WorkerState::Initializing(Board::new(), if options.use_hold { 3 } else { 2 });
-            while let Some(command) = recv.recv().await {
-                match &mut state {
-                    WorkerState::Initializing(board, pieces_left) => {
-                        if let InterfaceCommand::NewPiece(piece) = command {
-                            board.add_next_piece(piece);
-                            *pieces_left -= 1;
-                            if *pieces_left == 0 {
-                                let (options, evaluator) = interface_args.take().unwrap();
-                                let interface = cold_clear::Interface::launch(
-                                    &worker_uri,
-                                    board.clone(),
-                                    options,
-                                    evaluator
-                                ).await;
-                                state = WorkerState::Ready(interface);
-                            }
-                        }
-                    }
-                    WorkerState::Ready(interface) => {
-                        match command {
-                            InterfaceCommand::Reset { field, b2b, combo } => {
-                                interface.reset(field, b2b, combo);
-                            }
-                            InterfaceCommand::NewPiece(piece) => {
-                                interface.add_next_piece(piece);
-                            }
-                            InterfaceCommand::NextMove(incoming, send) => {
-                                interface.request_next_move(incoming);
-                                send.resolve(interface.next_move().await).unwrap();
-                            }
-                            InterfaceCommand::ForceAnalysisLine(line) => {
-                                interface.force_analysis_line(line);
-                            }
-                        }
-                    }
-                }
-            }
-        });
-        Ok(Self { send })
+        let status = Rc::new(WorkerStatus::new());
+        let worker = Worker::new(worker_uri, options, evaluator, on_ready, status.clone());
+        wasm_bindgen_futures::spawn_local(worker.run(recv));
+        Ok(Self {
+            send,
+            status,
+            pending: Default::default(),
+            piece_set,
+            standing_incoming: Cell::new(0),
+            origin
+        })
     }
     
     /// Request the bot to provide a move as soon as possible.
@@ -112,31 +373,217 @@ impl CCInterface {
     /// 
     /// Once a move is chosen, the bot will update its internal state to the result of the piece
     /// being placed correctly and the returned promise will resolve with the move. If the promise
-    /// returns `null`, the bot has died.
-    pub fn next_move(&self, incoming: u32) -> js_sys::Promise {
+    /// returns `null`, the bot has died, and the promises returned by any later calls are rejected
+    /// with a `WORKER_DEAD` error until it is revived with `reset` or `new_game`.
+    /// 
+    /// The move is provided as an array of the move itself, cold_clear's search info, and a summary
+    /// of the bot's plan with the following fields:
+    /// - `plan_lines_cleared`: the total number of lines cleared over the whole plan.
+    /// - `hold_reason`: a rough explanation of the hold decision, based on the best immediate
+    ///   placements with and without hold. It is `"none"` if the move didn't hold, `"avoid_death"` if
+    ///   the current piece couldn't be placed without topping out, `"better_piece"` if the held piece
+    ///   has the better placement, and `"setup"` if holding only pays off later in the plan.
+    /// - `confirmed_placements`: how many placements of the plan only use pieces the player can see.
+    /// - `speculative`: whether the plan goes on past the pieces the player can see, relying on
+    ///   pieces the bot was told about beyond `preview_count` or on speculation. Moves with a
+    ///   non-speculative plan can be trusted more.
+    /// - `sets_up_tspin`: whether the move isn't a T-spin itself, but improves a T-slot according to
+    ///   the evaluator's `tslot` weights and the plan goes on to T-spin later.
+    /// - `attack_per_piece`: the lines of garbage the plan sends divided by its number of
+    ///   placements, or `null` if the plan is empty. Garbage is counted the way cold_clear's attack
+    ///   model does, before any cancelling.
+    /// - `from_committed_plan`: whether the move was played from a plan kept by `commit_plan`
+    ///   instead of being searched for. The plan and search info are then those of the move the
+    ///   plan came from. This only says where the move came from, not how long it took: a
+    ///   searched move can resolve right away too if the bot had already searched enough.
+    /// - `restriction_broken`: whether the move soft drops despite `allow_soft_drop`, because
+    ///   no placement without soft drops survived. Such a move wasn't
+    ///   searched for, so its plan is only the move itself.
+    /// 
+    /// If `no_speculation` is true, the bot only considers the pieces it has been told about for
+    /// this move, even if speculation is enabled. This is done by relaunching the bot without
+    /// speculation and back, so previous computations are lost. Like any move, it needs the
+    /// current piece, and another with hold unless a piece is held: with fewer pieces, the promise
+    /// resolves with `null` right away without the bot dying, and the move can be asked for again
+    /// once more pieces have been added with `add_next_piece`.
+    pub fn next_move(&self, incoming: u32, no_speculation: Option<bool>) -> js_sys::Promise {
+        if let Err(error) = self.check_alive() {
+            return js_sys::Promise::reject(&error);
+        }
+        let no_speculation = no_speculation.unwrap_or(false);
+        let incoming = incoming.max(self.standing_incoming.get());
+        self.request(|send| InterfaceCommand::NextMove { incoming, no_speculation, send })
+    }
+    
+    /// Like `next_move`, but also provides the placement the bot plans to make after this one.
+    /// 
+    /// The promise resolves with an object containing the `move` and `info` that `next_move` would
+    /// provide, the `cells` that move's piece will occupy, and a `follow_up` placement (with its
+    /// `location` and `cells`) taken from the step of the bot's plan after the move. `follow_up` is
+    /// `null` if the plan doesn't go that far, or if the move isn't part of the plan because it
+    /// replaced one that was forbidden or broke a restriction. As with `next_move`, the promise
+    /// resolves with `null` if the bot has died.
+    pub fn next_two(&self, incoming: u32) -> js_sys::Promise {
+        if let Err(error) = self.check_alive() {
+            return js_sys::Promise::reject(&error);
+        }
+        let incoming = incoming.max(self.standing_incoming.get());
         let (send, recv) = oneshot();
-        // `Oneshot<T>` doesn't implement `Debug`, so in the meantime the error is discarded first.
-        self.send.send(InterfaceCommand::NextMove(incoming, send))
-            .map_err(|_| ())
-            .unwrap();
-        wasm_bindgen_futures::future_to_promise(async move {
-            Ok(JsValue::from_serde(&recv.await.unwrap()).unwrap())
+        let command = InterfaceCommand::NextMove { incoming, no_speculation: false, send };
+        if let Err(error) = self.send_command(command) {
+            return js_sys::Promise::reject(&error);
+        }
+        let origin = self.origin;
+        self.promise(async move {
+            let result = recv.await.unwrap().map(|(mv, info, summary)| {
+                // A move taken from a committed plan comes with the info of the move the plan came
+                // from, so it is somewhere further down that plan. A move that replaced the bot's
+                // choice isn't in the plan at all, and neither is its follow-up.
+                let plan = plan(&info);
+                let index = match summary.from_committed_plan {
+                    true => plan.iter()
+                        .skip(1)
+                        .position(|(location, _)| {
+                            analysis::same_placement(location, &mv.expected_location)
+                        })
+                        .map(|index| index + 1),
+                    false => plan.first()
+                        .filter(|(location, _)| {
+                            analysis::same_placement(location, &mv.expected_location)
+                        })
+                        .map(|_| 0)
+                };
+                let follow_up = index
+                    .and_then(|index| plan.get(index + 1))
+                    .map(|&(location, _)| PlannedPlacement::new(location, origin));
+                let cells = origin.cells(mv.expected_location.cells());
+                NextTwo { cells, mv, info, follow_up }
+            });
+            Ok(JsValue::from_serde(&result).unwrap())
         })
     }
-    
+
+    /// Provides the placements the bot intends to make next, without playing any of them.
+    /// 
+    /// The promise resolves with up to `pieces` placements, each with its `location` and `cells`,
+    /// starting with the current piece. The plan comes from a separate search of the bot's current
+    /// board with the same options and evaluator, so the running bot keeps its search tree.
+    /// That search needs its own worker and takes at least `min_nodes`, and other commands wait
+    /// for it to finish. The array is empty if the bot can't find any move, isn't ready yet, or
+    /// has too few pieces to pick one: fewer than 1, or 2 with hold and nothing held.
+    pub fn plan_ahead(&self, pieces: u32) -> js_sys::Promise {
+        let (send, recv) = oneshot();
+        if let Err(error) = self.send_command(InterfaceCommand::PlanAhead(pieces, send)) {
+            return js_sys::Promise::reject(&error);
+        }
+        let origin = self.origin;
+        self.promise(async move {
+            let plan: Vec<_> = recv.await.unwrap()
+                .into_iter()
+                .map(|location| PlannedPlacement::new(location, origin))
+                .collect();
+            Ok(JsValue::from_serde(&plan).unwrap())
+        })
+    }
+
+    /// Provides the placements it takes the bot to clear every row with garbage in it.
+    /// 
+    /// Garbage cells are those passed to `reset` or `new_game` as `true` or 8. The plan comes from
+    /// a separate search, as with `plan_ahead`, covering the pieces the bot knows about and any it
+    /// speculates on. The promise resolves with `{ placements, pieces }`, where `placements` is the
+    /// start of the plan up to the placement that clears the last garbage row, each with its
+    /// `location` and `cells`, and `pieces` is how many there are. Both are empty if there is no
+    /// garbage. The promise resolves with `null` if the plan doesn't clear all the garbage, which
+    /// includes when the bot has too few pieces to plan with, as with `plan_ahead`.
+    /// 
+    /// The search is the bot's usual one rather than one aiming for the garbage, so this is how the
+    /// bot would downstack, not the fastest way to do it.
+    pub fn downstack_plan(&self) -> js_sys::Promise {
+        let (send, recv) = oneshot();
+        if let Err(error) = self.send_command(InterfaceCommand::DownstackPlan(send)) {
+            return js_sys::Promise::reject(&error);
+        }
+        let origin = self.origin;
+        self.promise(async move {
+            let plan = recv.await.unwrap().map(|placements| DownstackPlan {
+                pieces: placements.len() as u32,
+                placements: placements.into_iter()
+                    .map(|location| PlannedPlacement::new(location, origin))
+                    .collect()
+            });
+            Ok(JsValue::from_serde(&plan).unwrap())
+        })
+    }
+
+    /// Provides the move the bot would make as a fumen quiz, for sharing the position as a puzzle.
+    /// 
+    /// The move comes from a separate search, as with `plan_ahead`, so the running bot keeps its
+    /// search tree and its board. `incoming` is the garbage to assume, as with `next_move`. The
+    /// promise resolves with a fumen string of a single page showing the bot's field and the
+    /// move's placement, with a quiz comment listing the hold piece, the current piece and the rest
+    /// of the queue. It resolves with `null` if the bot can't find any move, including when it has
+    /// too few pieces as with `plan_ahead`, or if the placement is above the 23 rows fumen can
+    /// show. Filled cells above those rows are left out of the field.
+    pub fn move_to_fumen_quiz(&self, incoming: u32) -> js_sys::Promise {
+        let incoming = incoming.max(self.standing_incoming.get());
+        self.request(|send| InterfaceCommand::FumenQuiz(incoming, send))
+    }
+
+    /// Sets the amount of garbage the bot should always assume is incoming.
+    /// 
+    /// Under sustained pressure, this lets the bot keep playing defensively even when a particular
+    /// `next_move` call is made with a lower `incoming`. Each move request uses the larger of the
+    /// two values, so pass 0 to go back to only considering the per-call value. cold_clear only
+    /// takes incoming garbage into account when a move is requested, so this has no effect on the
+    /// bot's thinking in between requests.
+    pub fn set_incoming(&self, amount: u32) {
+        self.standing_incoming.set(amount);
+    }
+
+    /// Returns the amount of garbage set with `set_incoming`.
+    pub fn incoming(&self) -> u32 {
+        self.standing_incoming.get()
+    }
+
+    /// Returns why the bot died, or `null` if it is still alive.
+    /// 
+    /// The reason is `"block_out"` if the next piece could not spawn because existing blocks are in
+    /// the way, or `"topout"` if the bot simply ran out of placements on a board that was too tall.
+    /// This is only known once a `next_move` promise has resolved with `null`.
+    pub fn death_reason(&self) -> JsValue {
+        JsValue::from_serde(&self.status.death.get()).unwrap()
+    }
+
+    /// Provides the number of milliseconds since the worker last finished processing a command.
+    /// 
+    /// The worker processes commands one at a time, so this keeps growing while it is busy with a
+    /// long `next_move`. A watchdog can use this to detect a wedged bot and relaunch it.
+    pub fn last_activity_ms(&self) -> js_sys::Promise {
+        let elapsed = js_sys::Date::now() - self.status.last_activity.get();
+        js_sys::Promise::resolve(&JsValue::from_f64(elapsed))
+    }
+
     /// Adds a new piece to the end of the queue.
     /// 
     /// If speculation is enabled, the piece *must* be in the bag. For example, if in the current
     /// bag you've provided the sequence IJOZT, then the next time you call this function you can
     /// only provide either an L or an S piece.
+    /// 
+    /// If a `piece_set` was provided at launch, the piece must be part of it.
+    /// 
+    /// Once the bot has died, this throws a `WORKER_DEAD` error instead of accepting the piece.
     pub fn add_next_piece(&mut self, piece: JsValue) -> Result<(), JsValue> {
+        self.check_alive()?;
         let piece = piece
             .into_serde()
             .map_err(to_js_error)?;
-        self.send.send(InterfaceCommand::NewPiece(piece))
-            .map_err(|_| ())
-            .unwrap();
-        Ok(())
+        if let Some(set) = &self.piece_set {
+            if !set.contains(&piece) {
+                let message = format!("`piece` must be one of {:?} (got {:?})", set, piece);
+                return Err(to_js_error(ArgumentError(message)));
+            }
+        }
+        self.send_command(InterfaceCommand::NewPiece(piece))
     }
 
     /// Resets the playfield, back-to-back status, and combo count.
@@ -149,25 +596,480 @@ impl CCInterface {
     /// number of consecutive line clears achieved. So, generally speaking, if "x Combo" appears
     /// on the screen, you need to use x+1 here.
     ///
-    /// `field` is an array of 40 rows, which are arrays of 10 bools. The first element is the
-    /// first row.
-    pub fn reset(&self, field: JsValue, b2b_active: bool, combo: u32) -> Result<(), JsValue> {
-        let src_field: Vec<[bool; 10]> = field
+    /// `field` is an array of 40 rows, which are arrays of 10 cells. The first element is the
+    /// bottom row, or the top row if `coordinate_origin` was set to `"top_left"` at launch. Cells
+    /// are either bools, where filled cells count as garbage, or the same numbers `cells`
+    /// provides, so that the bot can keep track of what fills each cell.
+    ///
+    /// `b2b` is either a bool or, for games that track back-to-back as a chain level, an integer.
+    /// The bot's attack model only knows whether back-to-back is active, so any level above 0
+    /// counts as active.
+    ///
+    /// Statistics such as `placement_stats` carry on across resets. Use `new_game` to clear them.
+    ///
+    /// Resetting also revives a bot that has died: `death_reason` goes back to `null`, and it can be
+    /// asked for moves again.
+    pub fn reset(&self, field: JsValue, b2b: JsValue, combo: u32) -> Result<(), JsValue> {
+        self.send_reset(field, b2b, combo, false)
+    }
+
+    /// Like `reset`, but for starting a new game on the same bot: statistics collected over the
+    /// previous game, namely `placement_stats`, are cleared as well.
+    pub fn new_game(&self, field: JsValue, b2b: JsValue, combo: u32) -> Result<(), JsValue> {
+        self.send_reset(field, b2b, combo, true)
+    }
+
+    /// Updates only the back-to-back status and combo count, keeping the playfield as is.
+    ///
+    /// This is meant for correcting counters your client miscounted. Before the bot has started
+    /// thinking, the counters are simply updated in place. Once it is running, cold_clear has no
+    /// way to change them without a reset, so the current search tree is discarded.
+    ///
+    /// The returned promise resolves once the new counters have been applied, so the next move
+    /// requested after that accounts for them. `b2b` is interpreted the same way as in `reset`.
+    pub fn set_counters(&self, b2b: JsValue, combo: u32) -> Result<js_sys::Promise, JsValue> {
+        let b2b_active = parse_b2b(b2b)?;
+        let (send, recv) = oneshot();
+        self.send_command(InterfaceCommand::SetCounters { b2b: b2b_active, combo, done: send })?;
+        Ok(self.promise(async move {
+            recv.await.unwrap();
+            Ok(JsValue::UNDEFINED)
+        }))
+    }
+
+    /// Biases the bot towards attacking or surviving.
+    /// 
+    /// `level` ranges from 0 (maximally defensive) to 1 (maximally aggressive), with 0.5 playing
+    /// exactly like the evaluator the bot was launched with. Towards 1, the rewards for line clears,
+    /// spins, perfect clears, combos and back-to-back are scaled up to twice their value, while the
+    /// penalties for clears that send little and the height and jeopardy penalties shrink towards
+    /// zero. Towards 0, it is the other way around.
+    /// 
+    /// Changing the evaluator forces the bot to throw away previous computations.
+    pub fn set_aggression(&self, level: f64) -> Result<(), JsValue> {
+        if !(0.0..=1.0).contains(&level) {
+            let message = format!("`level` must be between 0 and 1 (got {})", level);
+            return Err(to_js_error(ArgumentError(message)));
+        }
+        self.send_command(InterfaceCommand::SetAggression(level))
+    }
+
+    /// Turns speculation on or off for the rest of the game.
+    /// 
+    /// cold_clear can't prune only the speculative part of its search tree, so changing the setting
+    /// relaunches the bot, throwing away previous computations. Setting it to its current value
+    /// does nothing. Speculation can't be turned on if `piece_set` leaves out any piece. The
+    /// promise resolves once the change has been made.
+    pub fn set_speculate(&self, enabled: bool) -> js_sys::Promise {
+        self.request(|done| InterfaceCommand::SetSpeculate(enabled, done))
+    }
+
+    /// Forbids the bot from playing any of `placements`, an array of piece locations, on top of
+    /// those forbidden already.
+    /// 
+    /// A placement is forbidden if it fills the same cells with the same piece as one of them, no
+    /// matter its rotation state. cold_clear's search still considers them, so if the bot picks a
+    /// forbidden placement, the best placement according to the evaluator that is allowed is played
+    /// instead, as with `max_height`, discarding previous computations. If every placement is
+    /// forbidden, `next_move` resolves with `null`, but the bot hasn't died: it can still be asked
+    /// for moves once `clear_forbidden` or `reset` leaves it a placement.
+    /// 
+    /// An error is thrown if any placement has a cell outside the 10 by 40 field. The promise
+    /// resolves once the placements have been forbidden.
+    pub fn forbid_placements(&self, placements: JsValue) -> Result<js_sys::Promise, JsValue> {
+        let placements: Vec<FallingPiece> = placements
             .into_serde()
             .map_err(to_js_error)?;
-        let mut field = [[false; 10]; 40];
-        if src_field.len() != field.len() {
-            let message = format!("`field` must be 40 rows (got {})", src_field.len());
-            Err(to_js_error(ArgumentError(message)))
-        } else {
-            for (src, dest) in src_field.into_iter().zip(field.iter_mut()) {
-                *dest = src;
+        for (index, placement) in placements.iter().enumerate() {
+            let inside = placement.cells()
+                .iter()
+                .all(|&(x, y)| (0..10).contains(&x) && (0..40).contains(&y));
+            if !inside {
+                let message = format!("placement {} has cells outside the field", index);
+                return Err(to_js_error(ArgumentError(message)));
             }
-            self.send.send(InterfaceCommand::Reset { field, b2b: b2b_active, combo })
-                .map_err(|_| ())
-                .unwrap();
-            Ok(())
         }
+        Ok(self.request(|done| InterfaceCommand::ForbidPlacements(placements, done)))
+    }
+
+    /// Allows every placement forbidden by `forbid_placements` again. The promise resolves once
+    /// they have been allowed.
+    pub fn clear_forbidden(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::ClearForbidden)
+    }
+
+    /// Provides the options the bot is currently using.
+    /// 
+    /// These are the options passed to `launch` as the bot applied them, including cold_clear's
+    /// own options and changes made since, such as with `set_speculate`. Options that `launch`
+    /// derives from others are included as derived, for example `mode` once `instant_gravity` is
+    /// set. Passing the result back to `launch` gives a bot configured the same way. The evaluator
+    /// is provided separately by `export_evaluator`.
+    pub fn current_options(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::CurrentOptions)
+    }
+
+    /// Replaces the evaluator the bot was launched with.
+    /// 
+    /// Runtime adjustments such as `set_aggression` stay in effect on top of the new evaluator.
+    /// Changing the evaluator forces the bot to throw away previous computations, unless the new
+    /// evaluator is identical to the current one, in which case nothing happens. This makes it
+    /// cheap to call every time an external weights file changes, whether or not it really did.
+    pub fn set_evaluator(&self, evaluator: JsValue) -> Result<(), JsValue> {
+        let evaluator = evaluator
+            .into_serde()
+            .map_err(to_js_error)?;
+        self.send_command(InterfaceCommand::SetEvaluator(evaluator))
+    }
+
+    /// Provides the evaluator weights the bot was launched or last set with, adjusted by
+    /// `set_aggression`.
+    /// 
+    /// Adjustments that come from launch options, such as `speed_mode`, `reward_b2b` or
+    /// `preferred_wells`, and those made under pressure are left out, since `launch` applies them
+    /// again. The resolved value can be saved and passed back to `launch` with the same options to
+    /// get a bot that plays the same way.
+    pub fn export_evaluator(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::ExportEvaluator)
+    }
+
+    /// Ranks a placement of the current piece among every placement the bot could make.
+    /// 
+    /// The promise resolves with `{ rank, eval, candidates }`, where `rank` is 0 for the placement
+    /// the evaluator likes best and `candidates` is the number of placements considered, including
+    /// those using hold. It resolves with `null` if `placement` can't be reached. Placements are
+    /// ranked by the evaluator alone, without any search, since cold_clear doesn't expose how its
+    /// search tree rates them.
+    /// 
+    /// If `commit` is true and the placement is reachable, the bot then plays it as if it had
+    /// chosen it, throwing away previous computations.
+    pub fn rank_move(&self, placement: JsValue, commit: bool) -> Result<js_sys::Promise, JsValue> {
+        let placement = placement
+            .into_serde()
+            .map_err(to_js_error)?;
+        Ok(self.request(|send| InterfaceCommand::RankMove { placement, commit, send }))
+    }
+
+    /// Updates the bot's board with a placement made by someone else, such as the player in an
+    /// assist mode.
+    /// 
+    /// The placement must be of the current piece, or of the piece hold would give if hold is
+    /// enabled. The promise resolves with `true` once the bot has moved on to the board after the
+    /// placement, or with `false` if the placement can't be reached, in which case nothing changes.
+    /// Like a committed `rank_move`, this throws away previous computations.
+    pub fn observe_move(&self, placement: JsValue) -> Result<js_sys::Promise, JsValue> {
+        self.check_alive()?;
+        let placement = placement
+            .into_serde()
+            .map_err(to_js_error)?;
+        Ok(self.request(|send| InterfaceCommand::ObserveMove(placement, send)))
+    }
+
+    /// Scores every placement the bot could make right now, without searching.
+    /// 
+    /// The promise resolves with an array of placements of the current piece, and of the piece
+    /// hold would give if hold is enabled, each with its `location`, the `cells` it fills, whether
+    /// it requires a `hold` first, and its depth-1 `eval`. The array is sorted best first, and
+    /// placements with the same evaluation keep the order cold_clear's movement generation found
+    /// them in. Every reachable placement is listed, which is at most a few dozen per piece.
+    pub fn evaluate_all_placements(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::EvaluateAllPlacements)
+    }
+
+    /// Compares the bot's best placement of the current piece with its best placement after
+    /// holding, without searching.
+    /// 
+    /// The promise resolves with `{ play_now, hold }`, each the best placement of its kind as
+    /// `evaluate_all_placements` lists them, with its `location`, `cells`, `hold` and depth-1
+    /// `eval`. Either is `null` if there is no such placement, and `hold` is always `null` if hold
+    /// is disabled. cold_clear doesn't expose its search tree, so these are depth-1 evaluations
+    /// rather than the values of the bot's search.
+    pub fn hold_comparison(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::HoldComparison)
+    }
+
+    /// Finds the quickest way cold_clear knows to input a placement on the bot's current board.
+    /// 
+    /// The placement must be of the current piece, or of the piece hold would give if hold is
+    /// enabled. The promise resolves with `{ inputs, time, hold }`: the quickest sequence that
+    /// fills the same cells as `placement` according to cold_clear's movement generation, with the
+    /// fewest inputs breaking ties, the time it takes, and whether it needs a hold first. The
+    /// movement generation keeps one path to each position, the quickest it finds, so this isn't
+    /// necessarily the sequence with the fewest inputs, as finesse charts count them. The promise
+    /// is rejected if the placement can't be reached. The bot's movement mode is taken into account.
+    pub fn finesse(&self, placement: JsValue) -> Result<js_sys::Promise, JsValue> {
+        let placement = placement
+            .into_serde()
+            .map_err(to_js_error)?;
+        let (send, recv) = oneshot();
+        self.send_command(InterfaceCommand::Finesse(placement, send))?;
+        Ok(self.promise(async move {
+            match recv.await.unwrap() {
+                Some(finesse) => Ok(JsValue::from_serde(&finesse).unwrap()),
+                None => Err(to_js_error(ArgumentError("`placement` can't be reached")))
+            }
+        }))
+    }
+
+    /// Checks whether a placement can be reached and locked on the bot's current board.
+    /// 
+    /// The placement must be of the current piece, or of the piece hold would give if hold is
+    /// enabled. The promise resolves with a bool.
+    pub fn is_placement_legal(&self, placement: JsValue) -> Result<js_sys::Promise, JsValue> {
+        let placement = placement
+            .into_serde()
+            .map_err(to_js_error)?;
+        Ok(self.request(|send| InterfaceCommand::IsPlacementLegal(placement, send)))
+    }
+
+    /// Checks the bot's board and queue for states your client shouldn't be able to get it into.
+    /// 
+    /// The promise resolves with an array of messages describing each problem found, which is
+    /// empty if everything is consistent. The checks are that the known queue could come from a
+    /// 7-bag (skipped if `piece_set` leaves out any piece), that nothing is held if hold is
+    /// disabled, and that the field has no full rows. Messages are meant for people, not for
+    /// parsing.
+    pub fn integrity_check(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::IntegrityCheck)
+    }
+
+    /// Provides a string identifying the bot's current position, for caching results by position.
+    /// 
+    /// The key is URL-safe base64 and covers the field, hold piece, queue, back-to-back status and
+    /// combo. It is an exact encoding rather than a hash, so two positions get the same key
+    /// exactly when all of those are the same, and the same position always gets the same key.
+    /// Its length grows with the stack height and the queue length: a 20-row stack with 6 pieces
+    /// queued takes about 50 characters.
+    pub fn position_key(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::PositionKey)
+    }
+
+    /// Provides the cells of the bot's field that changed since the last call.
+    /// 
+    /// The promise resolves with an array of `{ row, col, filled }` for every cell that is filled
+    /// now but wasn't then, or the other way around, with rows in the same order as `reset`. The
+    /// first call reports every cell of the field.
+    pub fn field_delta(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::FieldDelta)
+    }
+
+    /// Provides the height of the tallest column of the bot's field, which is 0 if the field is
+    /// empty.
+    pub fn max_height(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::MaxHeight)
+    }
+
+    /// Provides statistics on how long the bot took to provide each move since launch or the last
+    /// `reset` or `new_game`.
+    /// 
+    /// The promise resolves with `{ moves, min_ms, max_ms, mean_ms, p95_ms }`, or `null` if the
+    /// bot hasn't provided a move yet. Times are measured in the worker from when it starts on a
+    /// `next_move` until the move is chosen, so they don't include time spent in the worker's
+    /// command queue. Moves played from a plan kept by `commit_plan` count as taking no time.
+    pub fn timing_stats(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::TimingStats)
+    }
+
+    /// Provides the empty cells of the bot's field that are covered by filled cells above them.
+    /// 
+    /// The promise resolves with an array of 10 columns from the left, each an array of its holes
+    /// from the bottom up as `{ row, covered_by }`, where `covered_by` is how many filled cells of
+    /// the column are above the hole. Rows follow `coordinate_origin` like the fields passed to
+    /// `reset`: counting up from 0 at the bottom by default, or down from 0 at the top of the
+    /// 40-row field with `"top_left"`.
+    pub fn holes(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::Holes)
+    }
+
+    /// Provides the bot's field with what fills each cell.
+    /// 
+    /// The promise resolves with an array of 40 rows of 10 numbers each, in the same row order as
+    /// `reset`: 0 for empty cells, 1 to 7 for cells filled by a piece (I, O, T, L, J, S, Z, the
+    /// piece's `color` in `piece_info` plus one), and 8 for garbage. Cells filled by a bool `true`
+    /// passed to `reset` count as garbage.
+    pub fn cells(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::Cells)
+    }
+
+    /// Provides the column (0 to 9, from the left) the bot is keeping open as its well.
+    /// 
+    /// This is the lowest column on the board, as long as every other column is filled on the row
+    /// right above it, which is how the evaluator identifies a well. The promise resolves with
+    /// `null` if there is no such column.
+    pub fn well_column(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::WellColumn)
+    }
+
+    /// Provides the evaluator's score for the bot's current board, without searching or playing.
+    /// 
+    /// Higher is better. This is only the value of the board itself: unlike the evaluations in
+    /// move results, it includes no reward for how the board was reached.
+    pub fn evaluate_current(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::EvaluateCurrent)
+    }
+
+    /// Rates each of the seven pieces by how much the bot would like to place it next.
+    /// 
+    /// Each piece is tried as if it were the only piece in the queue, on the bot's current field
+    /// with its hold piece and counters, and rated by the depth-1 evaluation of its best placement.
+    /// Holding it to place the hold piece instead isn't considered.
+    /// The promise resolves with an array of `{ piece, eval }` sorted best first, so the first
+    /// entry is the recommendation. `eval` is `null` for pieces that can only top out. This
+    /// doesn't search, so it is meant for item modes and drills rather than precise play.
+    pub fn best_next_piece(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::BestNextPiece)
+    }
+
+    /// Provides the pieces the bot's next move will place, depending on whether it holds.
+    /// 
+    /// The promise resolves with `{ current, hold }`: `current` is the piece at the front of the
+    /// queue, which is placed unless the move holds, and `hold` is the piece in the hold slot.
+    /// Either is `null` if the bot doesn't know of one. If the hold slot is empty, holding places
+    /// the piece after `current` instead.
+    pub fn active_piece(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::ActivePiece)
+    }
+
+    /// Provides the pieces that haven't been drawn from the current 7-bag yet, counting the pieces
+    /// passed to `add_next_piece` that the bot has been given. Pieces held back because of
+    /// `max_queue_consider` aren't counted until the bot is given them.
+    /// 
+    /// The promise resolves with an array of pieces in libtetris's piece order (I, O, T, L, J, S,
+    /// Z). Once a bag is used up, the next one starts full. This assumes a 7-bag randomizer, so it
+    /// means nothing if `piece_set` leaves out any piece.
+    pub fn bag_remaining(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::BagRemaining)
+    }
+
+    /// Provides a rough 0 to 1 rating of how healthy the bot's current board is.
+    /// 
+    /// This is `evaluate_current` mapped linearly onto 0 to 1, where 0 is a board the default
+    /// evaluator considers hopeless and 1 is about as good as an empty board. It is only a
+    /// heuristic meant for displays like a strength bar, not a win probability, and it is less
+    /// meaningful with custom evaluator weights.
+    pub fn strength_estimate(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::StrengthEstimate)
+    }
+
+    /// Provides the T-spin setups on the bot's current board, without searching.
+    /// 
+    /// The promise resolves with an array counting the T-slots the evaluator's `tslot` weights
+    /// would reward, indexed by the number of lines the T-spin would clear (0 to 3). The counts
+    /// come from the evaluator itself, so they cover exactly the slots it rewards when scoring a
+    /// board. The evaluator doesn't analyze spins of other pieces, so those can't be reported.
+    pub fn detect_spin_setups(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::SpinSetups)
+    }
+
+    /// Estimates how much `amount` lines of garbage would hurt the bot, without changing its board.
+    /// 
+    /// `columns` lists the garbage holes (0 to 9, from the left), starting with the first row to
+    /// arrive, and is repeated if it is shorter than `amount`. The promise resolves with the change
+    /// in `evaluate_current` the garbage would cause, which is negative if it makes the board
+    /// worse, or `null` if it would push the stack out of the field.
+    pub fn eval_after_garbage(
+        &self,
+        columns: Vec<u32>,
+        amount: u32
+    ) -> Result<js_sys::Promise, JsValue> {
+        if columns.is_empty() {
+            return Err(to_js_error(ArgumentError("`columns` must not be empty")));
+        }
+        if let Some(column) = columns.iter().find(|&&x| x >= 10) {
+            let message = format!("`columns` must be between 0 and 9 (got {})", column);
+            return Err(to_js_error(ArgumentError(message)));
+        }
+        // Any more than the 40 rows of the field tops out all the same.
+        let holes = columns.iter()
+            .map(|&x| x as usize)
+            .cycle()
+            .take(amount.min(40) as usize)
+            .collect();
+        Ok(self.request(|send| InterfaceCommand::EvalAfterGarbage(holes, send)))
+    }
+
+    /// Searches for a perfect clear using at most `max_placements` of the pieces the bot knows about.
+    /// 
+    /// The promise resolves with the sequence of placements leading to the perfect clear, each with
+    /// its `location`, the `cells` it fills and whether it requires a `hold` first, or `null` if no
+    /// perfect clear was found. Speculated pieces aren't considered.
+    /// 
+    /// The search tries every sequence, skipping boards it has already searched from, so it gets
+    /// much slower as `max_placements` grows. It gives up and resolves with `null` after trying
+    /// 200000 placements, so a `null` doesn't always mean there is no perfect clear. It
+    /// periodically yields to the event loop to keep the page responsive, but other commands sent
+    /// to the bot wait until it finishes.
+    pub fn find_perfect_clear(&self, max_placements: u32) -> js_sys::Promise {
+        self.request(|send| InterfaceCommand::FindPerfectClear(max_placements, send))
+    }
+
+    /// Provides the last 256 commands the worker processed, oldest first, if `debug` was enabled at
+    /// launch. Otherwise, the promise resolves with `null`.
+    /// 
+    /// Each entry has the `command` (named after the method that sent it) and a `board_hash` of the
+    /// bot's field, hold, queue, back-to-back status and combo once the command was processed.
+    /// Comparing these against your client's state helps track down where the two disagree.
+    pub fn debug_log(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::DebugLog)
+    }
+
+    /// Registers a callback for when the bot expects to top out soon.
+    /// 
+    /// Whenever the bot provides a move, `callback` is called if the plan it chose locks out
+    /// within `pieces_ahead` placements, with the number of placements until it does. Since
+    /// cold_clear picks the best plan it found, this means it found no way to survive that long,
+    /// though a deeper search, different pieces or less garbage could still save it. Passing
+    /// `null` as the callback unregisters it.
+    pub fn on_danger(
+        &self,
+        callback: Option<js_sys::Function>,
+        pieces_ahead: u32
+    ) -> Result<(), JsValue> {
+        let on_danger = callback.map(|callback| (callback, pieces_ahead));
+        self.send_command(InterfaceCommand::SetOnDanger(on_danger))
+    }
+
+    /// Registers a callback for every placement the bot makes, for recording replays.
+    /// 
+    /// `callback` is called after each placement with `{ field, hold, queue, b2b, combo, move }`:
+    /// the bot's board once the placement is made (with `field` in the same row order as `reset`)
+    /// and the `move` that made it. This includes placements played through `rank_move` and
+    /// `observe_move`, but not changes made by `reset`. Passing `null` unregisters the callback.
+    pub fn on_commit(&self, callback: Option<js_sys::Function>) -> Result<(), JsValue> {
+        self.send_command(InterfaceCommand::SetOnCommit(callback))
+    }
+
+    /// Registers a condition to stop at when debugging a specific board state.
+    /// 
+    /// `predicate` is an object with a `height` (met once any column is at least that tall)
+    /// and/or a number of `holes` (met once there are at least that many empty cells under the tops
+    /// of their columns). After every placement, if either condition is met, `on_breakpoint` is
+    /// called with the `position_key` of the board and the breakpoint is removed. Passing `null` as
+    /// `predicate` removes it without calling anything.
+    /// 
+    /// cold_clear keeps searching in its own worker no matter what, so the breakpoint can't pause
+    /// the bot itself. Stop asking it for moves from `on_breakpoint` to freeze the game there.
+    pub fn set_breakpoint(
+        &self,
+        predicate: JsValue,
+        on_breakpoint: js_sys::Function
+    ) -> Result<(), JsValue> {
+        let predicate: Option<analysis::Breakpoint> = predicate
+            .into_serde()
+            .map_err(to_js_error)?;
+        let breakpoint = predicate.map(|predicate| (predicate, on_breakpoint));
+        self.send_command(InterfaceCommand::SetBreakpoint(breakpoint))
+    }
+
+    /// Provides the last 64 errors thrown or rejected with, oldest first.
+    /// 
+    /// Each entry has the `time` the error was raised as a `Date.now()` timestamp, and the same
+    /// `name` and `message` as the error itself, which include the reason code for errors such as
+    /// `WORKER_DEAD`. The log is shared by every bot on the page, so that errors from static methods
+    /// and from bots that have been freed show up too.
+    pub fn error_log(&self) -> JsValue {
+        let log: Vec<ErrorEntry> = ERROR_LOG.with(|log| log.borrow().iter().cloned().collect());
+        JsValue::from_serde(&log).unwrap()
     }
 
     /// Specifies a line that Cold Clear should analyze before making any moves.
@@ -175,11 +1077,503 @@ impl CCInterface {
         let path = path
             .into_serde()
             .map_err(to_js_error)?;
-        self.send.send(InterfaceCommand::ForceAnalysisLine(path))
-            .map_err(|_| ())
-            .unwrap();
+        self.send_command(InterfaceCommand::ForceAnalysisLine(path))
+    }
+
+    /// Provides counts of how each piece type was placed since launch or the last `new_game`.
+    /// 
+    /// The promise resolves with an array in libtetris's piece order (I, O, T, L, J, S, Z). Each
+    /// entry has the `piece`, the number of times it was `placed`, and how many of those placements
+    /// were in each of the `rotations` (North, East, South, West) and with their leftmost cell in
+    /// each of the 10 `columns`. Placements made through `rank_move` and `observe_move` count too.
+    /// 
+    /// The counts are kept across `reset`, and cleared by `new_game`.
+    pub fn placement_stats(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::PlacementStats)
+    }
+
+    /// Provides every placement made since launch or the last `new_game`, in order.
+    /// 
+    /// The promise resolves with an array of `{ piece, rotation, x, y, hold }`, where `x` and `y`
+    /// are the piece's center in the same convention as a move's `expected_location`. Together
+    /// with the field and queue the game started from, this is enough to replay it. Like
+    /// `placement_stats`, placements made through `rank_move` and `observe_move` count too, and
+    /// the list is kept across `reset`, so garbage received in between isn't part of it.
+    pub fn export_game(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::ExportGame)
+    }
+
+    /// Provides the lines passed to `force_analysis_line` that the bot is still following.
+    /// 
+    /// The promise resolves with an array of lines, each an array of placements starting from the
+    /// bot's current piece. Once a piece is placed, lines that didn't start with that placement are
+    /// dropped, and the placement is removed from the front of the others. `reset` drops every line.
+    pub fn forced_lines(&self) -> js_sys::Promise {
+        self.request(InterfaceCommand::ForcedLines)
+    }
+
+    /// Launches a bot that plays `pieces` pieces on its own, calling `on_move` after every placement.
+    /// 
+    /// Pieces are dealt by a 7-bag randomizer seeded with `seed`, with 5 previews and no incoming
+    /// garbage, so runs with the same seed, options and evaluator are comparable. `on_move` receives
+    /// an object with the move's `piece` index, its `location`, the `nodes` searched (`null` for
+    /// perfect clear moves), search `depth`, `think_ms`, the `lines_cleared` and `garbage_sent` by
+    /// the move, and the resulting `field`.
+    /// 
+    /// The promise resolves once all pieces are placed or the bot dies, with the totals
+    /// `{ pieces_placed, lines_cleared, garbage_sent, garbage_received, dead }`, where
+    /// `garbage_received` is always 0. With hold, the last piece may be left unplaced, since the
+    /// bot needs another piece to consider holding it for. If `on_move` throws, the run stops and the promise is
+    /// rejected with the thrown value.
+    pub fn benchmark_stream(
+        worker_uri: String,
+        options: JsValue,
+        evaluator: JsValue,
+        pieces: u32,
+        seed: u32,
+        on_move: js_sys::Function
+    ) -> Result<js_sys::Promise, JsValue> {
+        let options: cold_clear::Options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        let evaluator: cold_clear::evaluation::Standard = evaluator
+            .into_serde()
+            .map_err(to_js_error)?;
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            let on_move = |stats: &selfplay::MoveStats| {
+                on_move.call1(&JsValue::NULL, &JsValue::from_serde(stats).unwrap()).map(drop)
+            };
+            let summary = selfplay::play(&worker_uri, options, evaluator, pieces, seed, 0.0, on_move)
+                .await?;
+            Ok(JsValue::from_serde(&summary).unwrap())
+        }))
+    }
+
+    /// Launches a bot that plays on its own under constant garbage pressure until it dies.
+    /// 
+    /// Pieces are dealt like in `benchmark_stream`. After every placement, `garbage_per_piece`
+    /// lines of garbage are added to the amount pending (so 0.25 is a line every 4 pieces), and
+    /// whole lines are pushed under the stack with a hole in a column picked using `seed`. The
+    /// bot's attack doesn't cancel incoming garbage. The promise resolves with the same totals as
+    /// `benchmark_stream`, where `pieces_placed` is how long the bot survived. Games stop at 10000
+    /// pieces if the bot never dies.
+    pub fn survival_test(
+        worker_uri: String,
+        options: JsValue,
+        evaluator: JsValue,
+        garbage_per_piece: f64,
+        seed: u32
+    ) -> Result<js_sys::Promise, JsValue> {
+        if garbage_per_piece.is_nan() || garbage_per_piece < 0.0 {
+            let message = format!(
+                "`garbage_per_piece` must be at least 0 (got {})",
+                garbage_per_piece
+            );
+            return Err(to_js_error(ArgumentError(message)));
+        }
+        let options: cold_clear::Options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        let evaluator: cold_clear::evaluation::Standard = evaluator
+            .into_serde()
+            .map_err(to_js_error)?;
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            let summary = selfplay::survival(
+                &worker_uri,
+                options,
+                evaluator,
+                garbage_per_piece,
+                seed
+            ).await?;
+            Ok(JsValue::from_serde(&summary).unwrap())
+        }))
+    }
+
+    /// Launches a bot on the given position just long enough to find its best move.
+    /// 
+    /// `field` is in the same format as in `reset` (bottom row first), `hold` is the held piece or
+    /// `null`, `queue` lists the known pieces starting with the current piece, and `b2b` and
+    /// `combo` are the counters as `reset` takes them. The promise resolves with `[move, info]`
+    /// for the move the bot picks with `incoming` lines of garbage coming, or `null` if it can't
+    /// survive. The worker is stopped afterwards, so each call pays the launch cost again.
+    /// 
+    /// An error is thrown if `queue` has fewer pieces than the bot needs to pick a move: the
+    /// current piece, and with hold, another to swap it for unless `hold` is a piece.
+    #[allow(clippy::too_many_arguments)]
+    pub fn best_move(
+        worker_uri: String,
+        field: JsValue,
+        hold: JsValue,
+        queue: JsValue,
+        b2b: JsValue,
+        combo: u32,
+        incoming: u32,
+        options: JsValue,
+        evaluator: JsValue
+    ) -> Result<js_sys::Promise, JsValue> {
+        let cells = parse_field(field)?;
+        let hold: Option<Piece> = hold
+            .into_serde()
+            .map_err(to_js_error)?;
+        let queue: Vec<Piece> = queue
+            .into_serde()
+            .map_err(to_js_error)?;
+        let b2b = parse_b2b(b2b)?;
+        let options: cold_clear::Options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        let evaluator: cold_clear::evaluation::Standard = evaluator
+            .into_serde()
+            .map_err(to_js_error)?;
+        let mut field = [[false; 10]; 40];
+        for (row, dest) in cells.iter().zip(field.iter_mut()) {
+            for (&cell, filled) in row.iter().zip(dest.iter_mut()) {
+                *filled = cell != EMPTY_CELL;
+            }
+        }
+        let mut board = Board::new();
+        board.set_field(field);
+        board.hold_piece = hold;
+        board.b2b_bonus = b2b;
+        board.combo = combo;
+        for &piece in &queue {
+            board.add_next_piece(piece);
+        }
+        // cold_clear would wait for the missing pieces, which can never be added.
+        if analysis::queue_too_short(&board, options.use_hold) {
+            let message = format!(
+                "`queue` must have a piece to place, and another with hold unless `hold` is set (got \
+                 {} pieces)",
+                queue.len()
+            );
+            return Err(to_js_error(ArgumentError(message)));
+        }
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            let mut interface = cold_clear::Interface::launch(&worker_uri, board, options, evaluator)
+                .await;
+            interface.request_next_move(incoming);
+            Ok(JsValue::from_serde(&interface.next_move().await).unwrap())
+        }))
+    }
+
+    /// Measures how long a bot takes from being launched to providing its first move.
+    /// 
+    /// The bot is launched from an empty board with the pieces in `queue`, current piece first. If
+    /// `queue` is empty, it gets the current piece and 5 previews from a 7-bag randomizer seeded
+    /// with `seed` instead. The promise resolves with `{ startup_ms, move }`, where `move` is
+    /// `null` if the bot couldn't find one. This includes the time it takes to start the worker,
+    /// so it is meant for comparing option presets on the same machine. After a minute without a
+    /// move, the bot is stopped and the promise resolves with a `null` move.
+    /// 
+    /// An error is thrown if `queue` isn't empty but still too short for the bot to pick a move:
+    /// it needs the current piece, and with hold, another to swap it for.
+    pub fn measure_startup(
+        worker_uri: String,
+        options: JsValue,
+        evaluator: JsValue,
+        queue: JsValue,
+        seed: u32
+    ) -> Result<js_sys::Promise, JsValue> {
+        let options: cold_clear::Options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        let evaluator: cold_clear::evaluation::Standard = evaluator
+            .into_serde()
+            .map_err(to_js_error)?;
+        let queue: Vec<Piece> = queue
+            .into_serde()
+            .map_err(to_js_error)?;
+        let mut board = Board::new();
+        for &piece in &queue {
+            board.add_next_piece(piece);
+        }
+        // cold_clear would wait for the missing pieces forever.
+        if !queue.is_empty() && analysis::queue_too_short(&board, options.use_hold) {
+            let message = format!(
+                "`queue` must have a piece to place, and another with hold (got {} pieces)",
+                queue.len()
+            );
+            return Err(to_js_error(ArgumentError(message)));
+        }
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            let startup = selfplay::measure_startup(&worker_uri, options, evaluator, queue, seed)
+                .await;
+            Ok(JsValue::from_serde(&startup).unwrap())
+        }))
+    }
+
+    /// Plays a game on its own and reports how closely it followed `reference_moves`, an array of
+    /// piece locations, to catch changes in the bot's behavior.
+    /// 
+    /// The game is played like in `benchmark_stream`, for as many pieces as there are reference
+    /// placements, except that pieces are dealt from `queue` first, and from the 7-bag seeded with
+    /// `seed` once it runs out. Each move is compared with the reference placement at the same
+    /// index, by the cells it fills. The game goes on with the bot's own moves after a mismatch,
+    /// so the boards differ from there on. The promise resolves with whether each move `matched`,
+    /// the `agreement` as the fraction of reference placements matched, and the index of the
+    /// `first_mismatch`, or `null` if there was none. If the bot dies, or stops because too few
+    /// pieces are left for it to move (with hold, the last one), the moves it didn't get to make
+    /// count as mismatches in `agreement` but are left out of `matched`.
+    pub fn compare_to_reference(
+        worker_uri: String,
+        reference_moves: JsValue,
+        options: JsValue,
+        evaluator: JsValue,
+        queue: JsValue,
+        seed: u32
+    ) -> Result<js_sys::Promise, JsValue> {
+        let reference: Vec<FallingPiece> = reference_moves
+            .into_serde()
+            .map_err(to_js_error)?;
+        let options: cold_clear::Options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        let evaluator: cold_clear::evaluation::Standard = evaluator
+            .into_serde()
+            .map_err(to_js_error)?;
+        let queue: Vec<Piece> = queue
+            .into_serde()
+            .map_err(to_js_error)?;
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            let comparison = selfplay::compare_to_reference(
+                &worker_uri,
+                options,
+                evaluator,
+                &reference,
+                queue,
+                seed
+            ).await?;
+            Ok(JsValue::from_serde(&comparison).unwrap())
+        }))
+    }
+
+    /// Plays the same seeded game with two evaluators and reports which one did better.
+    /// 
+    /// Each evaluator plays `pieces` pieces (or until it dies) the same way `benchmark_stream` does,
+    /// one after the other, on the exact same piece sequence. The promise resolves with both games'
+    /// totals as `a` and `b`, and `survived_longer`, `sent_more` and `cleared_more` set to `"a"`,
+    /// `"b"` or `"tie"`.
+    pub fn compare_evaluators(
+        worker_uri: String,
+        eval_a: JsValue,
+        eval_b: JsValue,
+        options: JsValue,
+        pieces: u32,
+        seed: u32
+    ) -> Result<js_sys::Promise, JsValue> {
+        let eval_a: cold_clear::evaluation::Standard = eval_a
+            .into_serde()
+            .map_err(to_js_error)?;
+        let eval_b: cold_clear::evaluation::Standard = eval_b
+            .into_serde()
+            .map_err(to_js_error)?;
+        let options: cold_clear::Options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            let a = selfplay::play(&worker_uri, options, eval_a, pieces, seed, 0.0, |_| Ok(()))
+                .await?;
+            let b = selfplay::play(&worker_uri, options, eval_b, pieces, seed, 0.0, |_| Ok(()))
+                .await?;
+            Ok(JsValue::from_serde(&selfplay::Comparison::new(a, b)).unwrap())
+        }))
+    }
+
+    /// Ranks several evaluators by how well they survive the same garbage.
+    /// 
+    /// `schedules` is an array of `{ garbage_per_piece, seed }` objects. Every evaluator plays a
+    /// game of `survival_test` with each of them, one game after the other on the same worker URI,
+    /// which can take a long time. An evaluator wins a schedule if no other evaluator survived
+    /// longer on it, so evaluators that tie for the longest all win. The promise resolves with an
+    /// array ranked by `wins`, then by `mean_pieces_placed`, where each entry has the `evaluator`'s
+    /// index in `evaluators`, its `wins`, its `mean_pieces_placed` over all schedules, and the
+    /// totals of its `games`, in the same order as `schedules`.
+    pub fn tournament(
+        worker_uri: String,
+        evaluators: JsValue,
+        options: JsValue,
+        schedules: JsValue
+    ) -> Result<js_sys::Promise, JsValue> {
+        let evaluators: Vec<cold_clear::evaluation::Standard> = evaluators
+            .into_serde()
+            .map_err(to_js_error)?;
+        let options: cold_clear::Options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        let schedules: Vec<selfplay::Schedule> = schedules
+            .into_serde()
+            .map_err(to_js_error)?;
+        let invalid = schedules.iter().find(|schedule| {
+            schedule.garbage_per_piece.is_nan() || schedule.garbage_per_piece < 0.0
+        });
+        if let Some(schedule) = invalid {
+            let message = format!(
+                "`garbage_per_piece` must be at least 0 (got {})",
+                schedule.garbage_per_piece
+            );
+            return Err(to_js_error(ArgumentError(message)));
+        }
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            let standings = selfplay::tournament(&worker_uri, options, evaluators, &schedules)
+                .await?;
+            Ok(JsValue::from_serde(&standings).unwrap())
+        }))
+    }
+
+    /// Packs a move, as provided by `next_move`, into 2 bytes for sending over the network.
+    /// 
+    /// Only the piece, its rotation, the column of its center and whether the move holds are kept;
+    /// `decode_move` turns the bytes back into those fields.
+    pub fn encode_move(mv: JsValue) -> Result<js_sys::Uint8Array, JsValue> {
+        let mv: cold_clear::Move = mv
+            .into_serde()
+            .map_err(to_js_error)?;
+        let bytes = encoding::encode(&mv)
+            .map_err(|message| to_js_error(ArgumentError(message)))?;
+        Ok(js_sys::Uint8Array::from(&bytes[..]))
+    }
+
+    /// Unpacks a move encoded by `encode_move` into an object with its `piece`, `rotation`, `x`
+    /// (the column of the piece's center) and `hold`.
+    pub fn decode_move(bytes: &[u8]) -> Result<JsValue, JsValue> {
+        let mv = encoding::decode(bytes)
+            .map_err(|message| to_js_error(ArgumentError(message)))?;
+        Ok(JsValue::from_serde(&mv).unwrap())
+    }
+
+    /// Checks that `value` names a piece the bot accepts, and provides it in the form the bot uses.
+    /// 
+    /// Piece names are accepted in either case, so `"t"` is normalized to `"T"`. Anything else
+    /// throws a `BAD_PIECE` error listing the valid pieces.
+    pub fn parse_piece(value: JsValue) -> Result<JsValue, JsValue> {
+        let name = value.as_string()
+            .map(|name| name.to_uppercase())
+            .ok_or(())
+            .and_then(|name| serde_json::from_value(name.into()).map_err(|_| ()));
+        let piece: Piece = name.map_err(|_| to_js_error(PieceError {
+            code: "BAD_PIECE",
+            valid: &encoding::PIECES
+        }))?;
+        Ok(JsValue::from_serde(&piece).unwrap())
+    }
+
+    /// Describes a piece as the bot sees it: its color index and its cells in each rotation state.
+    /// 
+    /// This doesn't need a running bot, so renderers can use it to stay consistent with the bot's
+    /// piece definitions.
+    pub fn piece_info(piece: JsValue) -> Result<JsValue, JsValue> {
+        let piece: Piece = piece
+            .into_serde()
+            .map_err(to_js_error)?;
+        let rotations = [
+            RotationState::North,
+            RotationState::East,
+            RotationState::South,
+            RotationState::West
+        ];
+        let info = PieceInfo {
+            piece,
+            color: piece as u8,
+            rotations: rotations.iter()
+                .map(|&rotation| RotationInfo {
+                    rotation,
+                    cells: PieceState(piece, rotation).cells()
+                })
+                .collect()
+        };
+        Ok(JsValue::from_serde(&info).unwrap())
+    }
+}
+
+impl CCInterface {
+    fn send_reset(
+        &self,
+        field: JsValue,
+        b2b: JsValue,
+        combo: u32,
+        new_game: bool
+    ) -> Result<(), JsValue> {
+        let b2b_active = parse_b2b(b2b)?;
+        let cells = self.origin.field(parse_field(field)?);
+        self.send_command(InterfaceCommand::Reset { cells, b2b: b2b_active, combo, new_game })?;
+        // The worker clears it too, but calls made before it gets to the reset shouldn't fail.
+        self.status.death.set(None);
         Ok(())
     }
+
+    /// Fails with a `WORKER_DEAD` error if the bot has died, since it can't do anything useful then.
+    fn check_alive(&self) -> Result<(), JsValue> {
+        match self.status.death.get() {
+            Some(_) => Err(to_js_error(WorkerError { code: "WORKER_DEAD" })),
+            None => Ok(())
+        }
+    }
+
+    /// Wraps `future` in a promise that is rejected with a `DISPOSED` error if the interface is
+    /// dropped before the future completes.
+    fn promise<F>(&self, future: F) -> js_sys::Promise
+    where
+        F: Future<Output = Result<JsValue, JsValue>> + 'static
+    {
+        let pending = self.pending.clone();
+        let mut future = Some(future);
+        js_sys::Promise::new(&mut |resolve, reject| {
+            let id = pending.borrow_mut().insert(reject);
+            let future = future.take().unwrap();
+            let pending = pending.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = future.await;
+                let reject = pending.borrow_mut().rejects.remove(&id);
+                // If the reject function is gone, the promise was already rejected on drop.
+                if let Some(reject) = reject {
+                    let settled = match result {
+                        Ok(value) => resolve.call1(&JsValue::UNDEFINED, &value),
+                        Err(error) => reject.call1(&JsValue::UNDEFINED, &error)
+                    };
+                    settled.unwrap();
+                }
+            });
+        })
+    }
+
+    /// Sends a command to the worker, failing with a `WORKER_BUSY` error if it no longer accepts
+    /// commands.
+    ///
+    /// The command channel is unbounded, so sending only fails once the worker has stopped, which
+    /// only happens if it panicked. Retrying wouldn't help then, but the failure is still reported
+    /// to the caller instead of taking down the whole module.
+    fn send_command(&self, command: InterfaceCommand) -> Result<(), JsValue> {
+        // `InterfaceCommand` doesn't implement `Debug`, so the error is discarded.
+        self.send.send(command)
+            .map_err(|_| to_js_error(WorkerError { code: "WORKER_BUSY" }))
+    }
+
+    /// Sends a command to the worker and returns a promise for the worker's response.
+    fn request<T, F>(&self, command: F) -> js_sys::Promise
+    where
+        T: Serialize + 'static,
+        F: FnOnce(Oneshot<T>) -> InterfaceCommand
+    {
+        let (send, recv) = oneshot();
+        if let Err(error) = self.send_command(command(send)) {
+            return js_sys::Promise::reject(&error);
+        }
+        self.promise(async move {
+            Ok(JsValue::from_serde(&recv.await.unwrap()).unwrap())
+        })
+    }
+}
+
+impl Drop for CCInterface {
+    /// Rejects every outstanding promise. Dropping the sender also closes the command channel,
+    /// which stops the worker once it finishes the command it is currently processing.
+    fn drop(&mut self) {
+        let rejects: Vec<_> = self.pending.borrow_mut().rejects.drain().collect();
+        for (_, reject) in rejects {
+            let error = to_js_error(WorkerError { code: "DISPOSED" });
+            reject.call1(&JsValue::UNDEFINED, &error).unwrap();
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -190,6 +1584,19 @@ impl CCOptions {
     pub fn default() -> JsValue {
         JsValue::from_serde(&cold_clear::Options::default()).unwrap()
     }
+
+    /// Checks an options object the same way `CCInterface::launch` does, without launching a bot.
+    /// 
+    /// Returns the options with every field filled in, or throws an error listing every invalid
+    /// field.
+    pub fn validate(options: JsValue) -> Result<JsValue, JsValue> {
+        let options = options
+            .into_serde()
+            .map_err(to_js_error)?;
+        let options = options::validate(options)
+            .map_err(|errors| to_js_error(ArgumentError(errors)))?;
+        Ok(JsValue::from_serde(&options).unwrap())
+    }
 }
 
 #[wasm_bindgen]
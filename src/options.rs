@@ -0,0 +1,269 @@
+use libtetris::*;
+use serde::{Serialize, Deserialize};
+
+/// Where row 0 of fields and cells passed to and from the bot is.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CoordinateOrigin {
+    /// Row 0 is the bottom row and y points up, as in libtetris.
+    #[default]
+    BottomLeft,
+    /// Row 0 is the top row of the 40-row field and y points down.
+    TopLeft
+}
+
+impl CoordinateOrigin {
+    /// Converts a cell between libtetris's coordinates and this convention, either way.
+    pub(crate) fn cell(self, (x, y): (i32, i32)) -> (i32, i32) {
+        match self {
+            CoordinateOrigin::BottomLeft => (x, y),
+            CoordinateOrigin::TopLeft => (x, 39 - y)
+        }
+    }
+
+    pub(crate) fn cells(self, cells: [(i32, i32); 4]) -> [(i32, i32); 4] {
+        let mut converted = cells;
+        for cell in &mut converted {
+            *cell = self.cell(*cell);
+        }
+        converted
+    }
+
+    /// Converts a field between libtetris's row order and this convention, either way.
+    pub(crate) fn field<T>(self, mut field: [[T; 10]; 40]) -> [[T; 10]; 40] {
+        if self == CoordinateOrigin::TopLeft {
+            field.reverse();
+        }
+        field
+    }
+}
+
+/// The options accepted by `CCInterface::launch`: cold_clear's own options, plus the ones this
+/// crate handles itself.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct LaunchOptions {
+    #[serde(flatten)]
+    pub(crate) cold_clear: cold_clear::Options,
+    /// The pieces the game's randomizer can produce, if it isn't a standard 7-bag.
+    #[serde(default)]
+    pub(crate) piece_set: Option<Vec<Piece>>,
+    /// Whether the worker should keep a log of the commands it processes.
+    #[serde(default)]
+    pub(crate) debug: bool,
+    /// Whether pieces drop instantly in the game, making soft drop inputs meaningless.
+    #[serde(default)]
+    pub(crate) instant_gravity: bool,
+    /// Whether the node limits should grow with the amount of incoming garbage.
+    #[serde(default)]
+    pub(crate) adaptive_nodes: bool,
+    /// Whether the survival weights should grow with the amount of incoming garbage.
+    #[serde(default)]
+    pub(crate) defensive_under_pressure: bool,
+    /// Whether the bot may use soft drops to reach placements.
+    #[serde(default = "enabled")]
+    pub(crate) allow_soft_drop: bool,
+    /// Whether the bot should favor placements that are quick to execute.
+    #[serde(default)]
+    pub(crate) speed_mode: bool,
+    /// The row pieces spawn on, overriding `spawn_rule`.
+    #[serde(default)]
+    pub(crate) spawn_row: Option<i32>,
+    /// The rotation state pieces spawn in.
+    #[serde(default)]
+    pub(crate) spawn_rotation: Option<RotationState>,
+    /// The number of rows the stack may never exceed.
+    #[serde(default)]
+    pub(crate) max_height: Option<i32>,
+    /// How many next pieces the game shows, if the client knows about more than that.
+    #[serde(default)]
+    pub(crate) preview_count: Option<u32>,
+    /// How many pieces of the queue, including the current piece, the bot is given at most.
+    #[serde(default)]
+    pub(crate) max_queue_consider: Option<u32>,
+    /// The coordinate convention of fields and cells.
+    #[serde(default)]
+    pub(crate) coordinate_origin: CoordinateOrigin,
+    /// Columns the bot should prefer to keep its well in.
+    #[serde(default)]
+    pub(crate) preferred_wells: Vec<usize>,
+    /// Whether the evaluator's back-to-back rewards apply.
+    #[serde(default = "enabled")]
+    pub(crate) reward_b2b: bool,
+    /// Whether the evaluator's combo reward applies.
+    #[serde(default = "enabled")]
+    pub(crate) reward_combo: bool,
+    /// How many placements of a plan the bot plays before searching again.
+    #[serde(default)]
+    pub(crate) commit_plan: u32,
+    /// Whether the bot should start right away instead of waiting for its first pieces.
+    #[serde(default)]
+    pub(crate) immediate_start: bool
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl LaunchOptions {
+    /// Whether the game can deal pieces the bot could not otherwise predict from a 7-bag.
+    pub(crate) fn restricted_pieces(&self) -> bool {
+        match &self.piece_set {
+            Some(set) => {
+                !crate::encoding::PIECES.iter().all(|piece| set.contains(piece))
+            }
+            None => false
+        }
+    }
+
+    /// How many of the pieces in `board`'s queue, starting with the current piece, the player can
+    /// actually see.
+    pub(crate) fn confirmed_pieces(&self, board: &Board) -> u32 {
+        let queued = board.next_queue().count() as u32;
+        match self.preview_count {
+            Some(previews) => queued.min(previews + 1),
+            None => queued
+        }
+    }
+
+    pub(crate) fn check_preferred_wells(&self) -> Result<(), String> {
+        match self.preferred_wells.iter().find(|&&x| x >= 10) {
+            Some(x) => Err(format!("`preferred_wells` must be columns 0 to 9 (got {})", x)),
+            None => Ok(())
+        }
+    }
+
+    /// The bot has to be given the pieces it waits for before it starts thinking.
+    pub(crate) fn check_max_queue_consider(&self) -> Result<(), String> {
+        // Even with `immediate_start`, the bot can only pick a move once it has a piece to place,
+        // and with hold, another it could swap it for.
+        let needed = match (self.cold_clear.use_hold, self.immediate_start) {
+            (true, false) => 3,
+            (true, true) | (false, false) => 2,
+            (false, true) => 1
+        };
+        match self.max_queue_consider {
+            Some(cap) if cap < needed => Err(format!(
+                "`max_queue_consider` must be at least {} (got {})",
+                needed,
+                cap
+            )),
+            _ => Ok(())
+        }
+    }
+
+    /// Translates the spawn overrides into the spawn rule cold_clear's movement generation uses.
+    ///
+    /// cold_clear only knows how to spawn pieces the way guideline games do, so only the spawn
+    /// positions those rules describe can be supported.
+    pub(crate) fn apply_spawn_overrides(&mut self) -> Result<(), String> {
+        match self.spawn_row {
+            // cold_clear has no rule for always spawning on row 20, and the guideline rule's
+            // fallback to row 20 is the closest it has.
+            Some(19) | Some(20) => self.cold_clear.spawn_rule = SpawnRule::Row19Or20,
+            Some(21) => self.cold_clear.spawn_rule = SpawnRule::Row21AndFall,
+            Some(row) => return Err(format!("`spawn_row` must be 19, 20 or 21 (got {})", row)),
+            None => {}
+        }
+        match self.spawn_rotation {
+            Some(RotationState::North) | None => Ok(()),
+            Some(rotation) => Err(format!("`spawn_rotation` must be North (got {:?})", rotation))
+        }
+    }
+}
+
+/// Checks every field of a launch options object on its own, so that all the problems with it can
+/// be reported at once instead of only the first one serde runs into.
+pub(crate) fn validate(options: serde_json::Value) -> Result<LaunchOptions, Vec<String>> {
+    let fields = match &options {
+        serde_json::Value::Object(fields) => fields,
+        _ => return Err(vec!["options must be an object".to_owned()])
+    };
+    let defaults = serde_json::to_value(LaunchOptions::default()).unwrap();
+    let mut errors = vec![];
+    for (name, value) in fields {
+        let mut single = defaults.clone();
+        single[name] = value.clone();
+        if let Err(error) = serde_json::from_value::<LaunchOptions>(single) {
+            errors.push(format!("`{}`: {}", name, error));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let parsed: LaunchOptions = serde_json::from_value(options)
+        .map_err(|error| vec![error.to_string()])?;
+    parsed.clone()
+        .apply_spawn_overrides()
+        .map_err(|error| vec![error])?;
+    parsed.check_preferred_wells()
+        .map_err(|error| vec![error])?;
+    parsed.check_max_queue_consider()
+        .map_err(|error| vec![error])?;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A complete options object with `changes` applied.
+    fn options(changes: serde_json::Value) -> serde_json::Value {
+        let mut options = serde_json::to_value(LaunchOptions::default()).unwrap();
+        for (name, value) in changes.as_object().unwrap() {
+            options[name] = value.clone();
+        }
+        options
+    }
+
+    fn errors(changes: serde_json::Value) -> Vec<String> {
+        validate(options(changes)).err().unwrap()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(validate(options(serde_json::json!({}))).is_ok());
+    }
+
+    #[test]
+    fn non_objects_are_rejected() {
+        assert_eq!(validate(serde_json::json!(5)).err().unwrap(), ["options must be an object"]);
+    }
+
+    #[test]
+    fn every_bad_field_is_reported() {
+        let errors = errors(serde_json::json!({ "debug": "yes", "max_height": "tall" }));
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|error| error.starts_with("`debug`")));
+        assert!(errors.iter().any(|error| error.starts_with("`max_height`")));
+    }
+
+    #[test]
+    fn cross_field_checks_run() {
+        assert_eq!(errors(serde_json::json!({ "spawn_row": 18 })).len(), 1);
+        assert_eq!(errors(serde_json::json!({ "preferred_wells": [10] })).len(), 1);
+        let short_queue = serde_json::json!({ "use_hold": true, "max_queue_consider": 2 });
+        assert_eq!(errors(short_queue).len(), 1);
+    }
+
+    #[test]
+    fn max_queue_consider_leaves_room_for_hold() {
+        let hold = |cap, immediate_start| validate(options(serde_json::json!({
+            "use_hold": true,
+            "immediate_start": immediate_start,
+            "max_queue_consider": cap
+        })));
+        assert!(hold(3, false).is_ok());
+        assert!(hold(2, true).is_ok());
+        assert!(hold(1, true).is_err());
+
+        let no_hold = |cap, immediate_start| validate(options(serde_json::json!({
+            "use_hold": false,
+            "immediate_start": immediate_start,
+            "max_queue_consider": cap
+        })));
+        assert!(no_hold(2, false).is_ok());
+        assert!(no_hold(1, false).is_err());
+        assert!(no_hold(1, true).is_ok());
+        assert!(no_hold(0, true).is_err());
+    }
+}
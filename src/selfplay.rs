@@ -0,0 +1,443 @@
+//! Games the bot plays on its own against a seeded randomizer, for benchmarking and comparisons.
+
+use libtetris::*;
+use cold_clear::evaluation::Standard;
+use serde::{Serialize, Deserialize};
+
+use crate::analysis::{add_garbage, queue_too_short, same_placement, with_timeout};
+use crate::encoding::PIECES;
+use crate::worker::apply_move;
+
+/// How many pieces of preview the bot gets during self-play.
+const PREVIEWS: usize = 5;
+
+/// How many pieces `survival` plays at most, in case the bot never dies.
+const SURVIVAL_PIECE_LIMIT: u32 = 10_000;
+
+/// A xorshift32 generator, so the same seed always produces the same numbers.
+struct Xorshift {
+    state: u32
+}
+
+impl Xorshift {
+    fn new(seed: u32) -> Self {
+        // Xorshift gets stuck on 0, so the seed is mixed with a constant first.
+        let state = seed ^ 0x9E37_79B9;
+        Self { state: if state == 0 { 1 } else { state } }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+}
+
+/// Derives a seed from `seed` for a second generator, with a splitmix64 step, so that its numbers
+/// aren't correlated with those of a generator seeded with `seed` itself.
+fn independent_seed(seed: u32) -> u32 {
+    let mut z = (seed as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((z ^ (z >> 31)) >> 32) as u32
+}
+
+/// A seeded 7-bag randomizer, so the same seed always deals the same pieces.
+pub(crate) struct SevenBag {
+    rng: Xorshift,
+    bag: Vec<Piece>
+}
+
+impl SevenBag {
+    pub(crate) fn new(seed: u32) -> Self {
+        Self {
+            rng: Xorshift::new(seed),
+            bag: vec![]
+        }
+    }
+}
+
+impl Iterator for SevenBag {
+    type Item = Piece;
+
+    fn next(&mut self) -> Option<Piece> {
+        if self.bag.is_empty() {
+            self.bag.extend_from_slice(&PIECES);
+        }
+        let index = self.rng.next_u32() as usize % self.bag.len();
+        Some(self.bag.swap_remove(index))
+    }
+}
+
+/// What happened when the bot placed a single piece.
+#[derive(Serialize)]
+pub(crate) struct MoveStats {
+    pub(crate) piece: usize,
+    pub(crate) location: FallingPiece,
+    /// Only known for moves found by the normal search.
+    pub(crate) nodes: Option<u32>,
+    pub(crate) depth: u32,
+    pub(crate) think_ms: f64,
+    pub(crate) lines_cleared: usize,
+    pub(crate) garbage_sent: u32,
+    /// The field after the move, first row first.
+    pub(crate) field: Vec<[bool; 10]>
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub(crate) struct GameSummary {
+    pub(crate) pieces_placed: u32,
+    pub(crate) lines_cleared: u32,
+    pub(crate) garbage_sent: u32,
+    pub(crate) garbage_received: u32,
+    pub(crate) dead: bool
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+pub(crate) enum Winner {
+    #[serde(rename = "a")]
+    A,
+    #[serde(rename = "b")]
+    B,
+    #[serde(rename = "tie")]
+    Tie
+}
+
+impl Winner {
+    fn by<T: Ord>(a: T, b: T) -> Self {
+        match a.cmp(&b) {
+            std::cmp::Ordering::Greater => Winner::A,
+            std::cmp::Ordering::Less => Winner::B,
+            std::cmp::Ordering::Equal => Winner::Tie
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct Comparison {
+    pub(crate) a: GameSummary,
+    pub(crate) b: GameSummary,
+    pub(crate) survived_longer: Winner,
+    pub(crate) sent_more: Winner,
+    pub(crate) cleared_more: Winner
+}
+
+impl Comparison {
+    pub(crate) fn new(a: GameSummary, b: GameSummary) -> Self {
+        Self {
+            a,
+            b,
+            survived_longer: Winner::by(a.pieces_placed, b.pieces_placed),
+            sent_more: Winner::by(a.garbage_sent, b.garbage_sent),
+            cleared_more: Winner::by(a.lines_cleared, b.lines_cleared)
+        }
+    }
+}
+
+fn search_stats(info: &cold_clear::Info) -> (Option<u32>, u32) {
+    match info {
+        cold_clear::Info::Normal(info) => (Some(info.nodes), info.depth),
+        cold_clear::Info::PcLoop(info) => (None, info.depth)
+    }
+}
+
+/// Plays `pieces` pieces dealt by a 7-bag seeded with `seed`, or until the bot dies.
+///
+/// After every placement, `garbage_per_piece` lines of garbage are added to the amount pending,
+/// and whole lines are inserted under the stack, with one hole per batch in a column also picked
+/// with `seed`. The bot's own attack doesn't cancel garbage. `on_move` is called after every
+/// placement. If it returns an error, the game stops and the error is passed along.
+///
+/// The game also stops once the bot has too few pieces left to pick a move, which with hold can
+/// leave the last piece unplaced, so fewer than `pieces` may be placed even if the bot survives.
+pub(crate) async fn play<F>(
+    worker_uri: &str,
+    options: cold_clear::Options,
+    evaluator: Standard,
+    pieces: u32,
+    seed: u32,
+    garbage_per_piece: f64,
+    on_move: F
+) -> Result<GameSummary, wasm_bindgen::JsValue>
+where
+    F: FnMut(&MoveStats) -> Result<(), wasm_bindgen::JsValue>
+{
+    let randomizer = SevenBag::new(seed);
+    play_dealt(worker_uri, options, evaluator, pieces, randomizer, seed, garbage_per_piece, on_move)
+        .await
+}
+
+/// Like `play`, but with the pieces dealt by `randomizer` instead. If it runs out, no more pieces
+/// are dealt, and the game stops like when `pieces` have been dealt. Garbage holes are still picked
+/// with `seed`.
+#[allow(clippy::too_many_arguments)]
+async fn play_dealt<F>(
+    worker_uri: &str,
+    options: cold_clear::Options,
+    evaluator: Standard,
+    pieces: u32,
+    mut randomizer: impl Iterator<Item = Piece>,
+    seed: u32,
+    garbage_per_piece: f64,
+    mut on_move: F
+) -> Result<GameSummary, wasm_bindgen::JsValue>
+where
+    F: FnMut(&MoveStats) -> Result<(), wasm_bindgen::JsValue>
+{
+    let mut holes = Xorshift::new(independent_seed(seed));
+    let mut pending_garbage = 0.0;
+    let mut board = Board::new();
+    let mut dealt = 0;
+    // The current piece, then the previews.
+    while dealt < pieces && (dealt as usize) < PREVIEWS + 1 {
+        match randomizer.next() {
+            Some(piece) => board.add_next_piece(piece),
+            None => break
+        }
+        dealt += 1;
+    }
+
+    let mut summary = GameSummary {
+        pieces_placed: 0,
+        lines_cleared: 0,
+        garbage_sent: 0,
+        garbage_received: 0,
+        dead: false
+    };
+    // cold_clear would wait forever for pieces that are never dealt.
+    if queue_too_short(&board, options.use_hold) {
+        return Ok(summary);
+    }
+    let mut interface = cold_clear::Interface::launch(
+        worker_uri,
+        board.clone(),
+        options,
+        evaluator
+    ).await;
+
+    while summary.pieces_placed < pieces && !queue_too_short(&board, options.use_hold) {
+        let start = js_sys::Date::now();
+        interface.request_next_move(0);
+        let (mv, info) = match interface.next_move().await {
+            Some(result) => result,
+            None => {
+                summary.dead = true;
+                break;
+            }
+        };
+        let think_ms = js_sys::Date::now() - start;
+        let lock = apply_move(&mut board, &mv);
+        pending_garbage += garbage_per_piece;
+        let lines = (pending_garbage as usize).min(board.get_field().len());
+        let mut topped_out = false;
+        if lines > 0 {
+            pending_garbage -= lines as f64;
+            summary.garbage_received += lines as u32;
+            let hole = holes.next_u32() as usize % 10;
+            topped_out = !add_garbage(&mut board, &vec![hole; lines]);
+            if !topped_out {
+                interface.reset(board.get_field(), board.b2b_bonus, board.combo);
+            }
+        }
+        let next = match dealt < pieces && !topped_out {
+            true => randomizer.next(),
+            false => None
+        };
+        if let Some(piece) = next {
+            board.add_next_piece(piece);
+            interface.add_next_piece(piece);
+            dealt += 1;
+        }
+
+        summary.pieces_placed += 1;
+        summary.lines_cleared += lock.cleared_lines.len() as u32;
+        summary.garbage_sent += lock.garbage_sent;
+        let (nodes, depth) = search_stats(&info);
+        on_move(&MoveStats {
+            piece: summary.pieces_placed as usize - 1,
+            location: mv.expected_location,
+            nodes,
+            depth,
+            think_ms,
+            lines_cleared: lock.cleared_lines.len(),
+            garbage_sent: lock.garbage_sent,
+            field: board.get_field().to_vec()
+        })?;
+        // The move itself still counts in full, garbage only tops the bot out after it.
+        if topped_out {
+            summary.dead = true;
+            break;
+        }
+    }
+    Ok(summary)
+}
+
+#[derive(Serialize)]
+pub(crate) struct Startup {
+    pub(crate) startup_ms: f64,
+    /// `None` if the bot couldn't find a move at all, or took too long.
+    #[serde(rename = "move")]
+    pub(crate) mv: Option<cold_clear::Move>
+}
+
+/// How long `measure_startup` waits for the first move before giving up.
+const STARTUP_TIMEOUT_MS: f64 = 60_000.0;
+
+/// Times how long a freshly launched bot takes to provide its first move.
+///
+/// The bot is given `queue`, or if it is empty, the first pieces dealt by a 7-bag seeded with
+/// `seed`. The clock starts right before launching. The bot is stopped and no move is reported if
+/// it takes longer than `STARTUP_TIMEOUT_MS`.
+pub(crate) async fn measure_startup(
+    worker_uri: &str,
+    options: cold_clear::Options,
+    evaluator: Standard,
+    queue: Vec<Piece>,
+    seed: u32
+) -> Startup {
+    let queue = match queue.is_empty() {
+        true => SevenBag::new(seed).take(PREVIEWS + 1).collect(),
+        false => queue
+    };
+    let mut board = Board::new();
+    for piece in queue {
+        board.add_next_piece(piece);
+    }
+    let start = js_sys::Date::now();
+    let first_move = async {
+        let mut interface = cold_clear::Interface::launch(worker_uri, board, options, evaluator)
+            .await;
+        interface.request_next_move(0);
+        interface.next_move().await.map(|(mv, _)| mv)
+    };
+    let mv = with_timeout(STARTUP_TIMEOUT_MS, first_move).await.flatten();
+    Startup { startup_ms: js_sys::Date::now() - start, mv }
+}
+
+/// Plays like `play` under a constant garbage rate until the bot dies, up to
+/// `SURVIVAL_PIECE_LIMIT` pieces.
+pub(crate) async fn survival(
+    worker_uri: &str,
+    options: cold_clear::Options,
+    evaluator: Standard,
+    garbage_per_piece: f64,
+    seed: u32
+) -> Result<GameSummary, wasm_bindgen::JsValue> {
+    play(
+        worker_uri,
+        options,
+        evaluator,
+        SURVIVAL_PIECE_LIMIT,
+        seed,
+        garbage_per_piece,
+        |_| Ok(())
+    ).await
+}
+
+/// The garbage a tournament game is played under, as passed to `survival`.
+#[derive(Deserialize, Clone, Copy)]
+pub(crate) struct Schedule {
+    pub(crate) garbage_per_piece: f64,
+    pub(crate) seed: u32
+}
+
+/// How one evaluator did in a tournament.
+#[derive(Serialize)]
+pub(crate) struct Standing {
+    /// The evaluator's index in the list it was entered in.
+    evaluator: usize,
+    /// How many schedules no other evaluator survived longer on.
+    wins: u32,
+    mean_pieces_placed: f64,
+    /// One game per schedule, in order.
+    games: Vec<GameSummary>
+}
+
+/// Plays a `survival` game with every evaluator on every schedule, and ranks the evaluators by
+/// wins, then by how long they survived on average.
+pub(crate) async fn tournament(
+    worker_uri: &str,
+    options: cold_clear::Options,
+    evaluators: Vec<Standard>,
+    schedules: &[Schedule]
+) -> Result<Vec<Standing>, wasm_bindgen::JsValue> {
+    let mut standings = vec![];
+    for (index, evaluator) in evaluators.into_iter().enumerate() {
+        let mut games = vec![];
+        for schedule in schedules {
+            let game = survival(
+                worker_uri,
+                options,
+                evaluator.clone(),
+                schedule.garbage_per_piece,
+                schedule.seed
+            ).await?;
+            games.push(game);
+        }
+        let total: u32 = games.iter().map(|game| game.pieces_placed).sum();
+        standings.push(Standing {
+            evaluator: index,
+            wins: 0,
+            mean_pieces_placed: total as f64 / games.len().max(1) as f64,
+            games
+        });
+    }
+
+    for game in 0..schedules.len() {
+        let best = standings.iter().map(|standing| standing.games[game].pieces_placed).max();
+        for standing in &mut standings {
+            if Some(standing.games[game].pieces_placed) == best {
+                standing.wins += 1;
+            }
+        }
+    }
+    standings.sort_by(|a, b| {
+        b.wins.cmp(&a.wins)
+            .then(b.mean_pieces_placed.partial_cmp(&a.mean_pieces_placed).unwrap())
+    });
+    Ok(standings)
+}
+
+/// How closely the bot's moves followed a list of reference placements.
+#[derive(Serialize)]
+pub(crate) struct ReferenceComparison {
+    /// Whether each move the bot made was the reference placement, in order.
+    matched: Vec<bool>,
+    /// The fraction of the reference placements the bot matched.
+    agreement: f64,
+    first_mismatch: Option<usize>
+}
+
+/// Plays a game like `play`, with as many pieces as there are `reference` placements, dealt from
+/// `queue` and then from a 7-bag seeded with `seed`, and compares every move with the reference.
+pub(crate) async fn compare_to_reference(
+    worker_uri: &str,
+    options: cold_clear::Options,
+    evaluator: Standard,
+    reference: &[FallingPiece],
+    queue: Vec<Piece>,
+    seed: u32
+) -> Result<ReferenceComparison, wasm_bindgen::JsValue> {
+    let randomizer = queue.into_iter().chain(SevenBag::new(seed));
+    let mut matched = vec![];
+    play_dealt(
+        worker_uri,
+        options,
+        evaluator,
+        reference.len() as u32,
+        randomizer,
+        seed,
+        0.0,
+        |stats| {
+            matched.push(same_placement(&stats.location, &reference[stats.piece]));
+            Ok(())
+        }
+    ).await?;
+    let agreed = matched.iter().filter(|&&matched| matched).count();
+    Ok(ReferenceComparison {
+        agreement: agreed as f64 / reference.len().max(1) as f64,
+        first_mismatch: matched.iter().position(|&matched| !matched),
+        matched
+    })
+}
@@ -0,0 +1,154 @@
+//! Adjustments layered over the evaluator a bot was launched with.
+
+use cold_clear::evaluation::Standard;
+
+fn scale(weight: &mut i32, factor: f64) {
+    *weight = (*weight as f64 * factor).round() as i32;
+}
+
+/// Scales a weight towards the direction it pushes the bot in: a positive weight is multiplied by
+/// `factor`, and a negative one by `2 - factor`, so a factor above 1 makes either more favorable.
+fn scale_reward(weight: &mut i32, factor: f64) {
+    match *weight >= 0 {
+        true => scale(weight, factor),
+        false => scale(weight, 2.0 - factor)
+    }
+}
+
+/// Scales attack and survival weights according to an aggression level between 0 and 1.
+///
+/// At 0.5 the evaluator is left as is. Towards 1 the rewards for line clears, spins, perfect
+/// clears, combos and back-to-back are scaled up to twice their value, the penalties for clears
+/// that send little (in the default weights, singles, doubles, triples and minis) shrink to
+/// nothing, and so do the height and jeopardy penalties; towards 0 it is the other way around.
+pub(crate) fn apply_aggression(evaluator: &mut Standard, level: f64) {
+    let attack = 2.0 * level;
+    let survival = 2.0 * (1.0 - level);
+    for weight in &mut [
+        &mut evaluator.back_to_back,
+        &mut evaluator.b2b_clear,
+        &mut evaluator.clear1,
+        &mut evaluator.clear2,
+        &mut evaluator.clear3,
+        &mut evaluator.clear4,
+        &mut evaluator.tspin1,
+        &mut evaluator.tspin2,
+        &mut evaluator.tspin3,
+        &mut evaluator.mini_tspin1,
+        &mut evaluator.mini_tspin2,
+        &mut evaluator.perfect_clear,
+        &mut evaluator.combo_garbage
+    ] {
+        scale_reward(weight, attack);
+    }
+    for weight in evaluator.tslot.iter_mut() {
+        scale_reward(weight, attack);
+    }
+    for weight in &mut [
+        &mut evaluator.height,
+        &mut evaluator.top_half,
+        &mut evaluator.top_quarter,
+        &mut evaluator.jeopardy
+    ] {
+        scale(weight, survival);
+    }
+}
+
+/// Multiplies the height and jeopardy penalties by `factor`, leaving attack weights alone.
+pub(crate) fn apply_defense(evaluator: &mut Standard, factor: f64) {
+    for weight in &mut [
+        &mut evaluator.height,
+        &mut evaluator.top_half,
+        &mut evaluator.top_quarter,
+        &mut evaluator.jeopardy
+    ] {
+        scale(weight, factor);
+    }
+}
+
+/// Stops the evaluator from valuing back-to-back, so it doesn't chase it at the expense of
+/// efficiency.
+pub(crate) fn remove_b2b_reward(evaluator: &mut Standard) {
+    evaluator.back_to_back = 0;
+    evaluator.b2b_clear = 0;
+}
+
+/// Stops the evaluator from valuing combos.
+pub(crate) fn remove_combo_reward(evaluator: &mut Standard) {
+    evaluator.combo_garbage = 0;
+}
+
+/// How much more `move_time` is penalized in speed mode.
+const SPEED_MODE_MOVE_TIME_FACTOR: f64 = 4.0;
+
+/// Makes the evaluator care more about how long a placement takes to input.
+pub(crate) fn apply_speed_mode(evaluator: &mut Standard) {
+    scale(&mut evaluator.move_time, SPEED_MODE_MOVE_TIME_FACTOR);
+}
+
+/// How much a preferred well column is favored over the column the evaluator liked best.
+const PREFERRED_WELL_BONUS: i32 = 50;
+
+/// Makes the evaluator favor keeping its well in one of `columns`.
+///
+/// Each of them is given the best of the evaluator's well column weights, plus a bonus, so the
+/// evaluator prefers them over every other column while keeping its existing preferences among
+/// the rest.
+pub(crate) fn apply_preferred_wells(evaluator: &mut Standard, columns: &[usize]) {
+    let best = *evaluator.well_column.iter().max().unwrap();
+    for &x in columns {
+        evaluator.well_column[x] = best + PREFERRED_WELL_BONUS;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggression_favors_every_clear() {
+        let default = Standard::default();
+        let mut aggressive = default.clone();
+        apply_aggression(&mut aggressive, 1.0);
+        let clears = |evaluator: &Standard| [
+            evaluator.clear1,
+            evaluator.clear2,
+            evaluator.clear3,
+            evaluator.clear4,
+            evaluator.tspin1,
+            evaluator.tspin2,
+            evaluator.tspin3,
+            evaluator.mini_tspin1,
+            evaluator.mini_tspin2,
+            evaluator.perfect_clear
+        ];
+        for (before, after) in clears(&default).iter().zip(clears(&aggressive).iter()) {
+            assert!(after >= before, "{} became {}", before, after);
+        }
+        assert!(aggressive.clear1 > default.clear1);
+        assert!(aggressive.clear4 > default.clear4);
+        assert_eq!(aggressive.height, 0);
+        assert_eq!(aggressive.jeopardy, 0);
+    }
+
+    #[test]
+    fn defensive_aggression_disfavors_clears() {
+        let default = Standard::default();
+        let mut defensive = default.clone();
+        apply_aggression(&mut defensive, 0.0);
+        assert!(defensive.clear1 < default.clear1);
+        assert!(defensive.tspin2 < default.tspin2);
+        assert!(defensive.height < default.height);
+    }
+
+    #[test]
+    fn half_aggression_changes_nothing() {
+        let default = Standard::default();
+        let mut evaluator = default.clone();
+        apply_aggression(&mut evaluator, 0.5);
+        assert_eq!(evaluator.clear1, default.clear1);
+        assert_eq!(evaluator.clear4, default.clear4);
+        assert_eq!(evaluator.tslot, default.tslot);
+        assert_eq!(evaluator.top_quarter, default.top_quarter);
+    }
+}
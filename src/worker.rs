@@ -0,0 +1,1269 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use webutil::channel::{Receiver, Oneshot};
+use libtetris::*;
+use cold_clear::evaluation::Standard;
+use serde::Serialize;
+
+use crate::DeathReason;
+use crate::encoding::{PIECES, ROTATIONS};
+use crate::options::LaunchOptions;
+use crate::analysis::{
+    self, Breakpoint, Finesse, Hole, HoldComparison, HoldReason, MoveRank, PieceValue,
+    ScoredPlacement, Step
+};
+
+pub(crate) enum InterfaceCommand {
+    Reset {
+        cells: [[u8; 10]; 40],
+        b2b: bool,
+        combo: u32,
+        /// Whether statistics about the previous game should be cleared too.
+        new_game: bool
+    },
+    SetCounters {
+        b2b: bool,
+        combo: u32,
+        done: Oneshot<()>
+    },
+    NewPiece(Piece),
+    NextMove {
+        incoming: u32,
+        no_speculation: bool,
+        send: Oneshot<Option<MoveResult>>
+    },
+    ForceAnalysisLine(Vec<FallingPiece>),
+    SetAggression(f64),
+    ExportEvaluator(Oneshot<Standard>),
+    RankMove {
+        placement: FallingPiece,
+        commit: bool,
+        send: Oneshot<Option<MoveRank>>
+    },
+    WellColumn(Oneshot<Option<usize>>),
+    DebugLog(Oneshot<Option<Vec<DebugEntry>>>),
+    EvaluateCurrent(Oneshot<i32>),
+    SetEvaluator(Standard),
+    FindPerfectClear(u32, Oneshot<Option<Vec<Step>>>),
+    IsPlacementLegal(FallingPiece, Oneshot<bool>),
+    ObserveMove(FallingPiece, Oneshot<bool>),
+    ForcedLines(Oneshot<Vec<Vec<FallingPiece>>>),
+    PlacementStats(Oneshot<PlacementStats>),
+    StrengthEstimate(Oneshot<f64>),
+    BagRemaining(Oneshot<Vec<Piece>>),
+    ActivePiece(Oneshot<ActivePiece>),
+    SetSpeculate(bool, Oneshot<()>),
+    EvaluateAllPlacements(Oneshot<Vec<ScoredPlacement>>),
+    IntegrityCheck(Oneshot<Vec<String>>),
+    Cells(Oneshot<[[u8; 10]; 40]>),
+    Finesse(FallingPiece, Oneshot<Option<Finesse>>),
+    SetOnDanger(Option<(js_sys::Function, u32)>),
+    PositionKey(Oneshot<String>),
+    SetOnCommit(Option<js_sys::Function>),
+    SetBreakpoint(Option<(Breakpoint, js_sys::Function)>),
+    CurrentOptions(Oneshot<LaunchOptions>),
+    PlanAhead(u32, Oneshot<Vec<FallingPiece>>),
+    BestNextPiece(Oneshot<Vec<PieceValue>>),
+    EvalAfterGarbage(Vec<usize>, Oneshot<Option<i32>>),
+    FieldDelta(Oneshot<Vec<CellChange>>),
+    MaxHeight(Oneshot<i32>),
+    TimingStats(Oneshot<Option<TimingStats>>),
+    ForbidPlacements(Vec<FallingPiece>, Oneshot<()>),
+    ClearForbidden(Oneshot<()>),
+    SpinSetups(Oneshot<[i32; 4]>),
+    FumenQuiz(u32, Oneshot<Option<String>>),
+    DownstackPlan(Oneshot<Option<Vec<FallingPiece>>>),
+    HoldComparison(Oneshot<HoldComparison>),
+    ExportGame(Oneshot<Vec<GamePlacement>>),
+    Holes(Oneshot<Vec<Vec<Hole>>>)
+}
+
+impl InterfaceCommand {
+    fn name(&self) -> &'static str {
+        match self {
+            InterfaceCommand::Reset { new_game: false, .. } => "reset",
+            InterfaceCommand::Reset { new_game: true, .. } => "new_game",
+            InterfaceCommand::SetCounters { .. } => "set_counters",
+            InterfaceCommand::NewPiece(_) => "add_next_piece",
+            InterfaceCommand::NextMove { .. } => "next_move",
+            InterfaceCommand::ForceAnalysisLine(_) => "force_analysis_line",
+            InterfaceCommand::SetAggression(_) => "set_aggression",
+            InterfaceCommand::ExportEvaluator(_) => "export_evaluator",
+            InterfaceCommand::RankMove { .. } => "rank_move",
+            InterfaceCommand::WellColumn(_) => "well_column",
+            InterfaceCommand::DebugLog(_) => "debug_log",
+            InterfaceCommand::EvaluateCurrent(_) => "evaluate_current",
+            InterfaceCommand::SetEvaluator(_) => "set_evaluator",
+            InterfaceCommand::FindPerfectClear(..) => "find_perfect_clear",
+            InterfaceCommand::IsPlacementLegal(..) => "is_placement_legal",
+            InterfaceCommand::ObserveMove(..) => "observe_move",
+            InterfaceCommand::ForcedLines(_) => "forced_lines",
+            InterfaceCommand::PlacementStats(_) => "placement_stats",
+            InterfaceCommand::StrengthEstimate(_) => "strength_estimate",
+            InterfaceCommand::BagRemaining(_) => "bag_remaining",
+            InterfaceCommand::ActivePiece(_) => "active_piece",
+            InterfaceCommand::SetSpeculate(..) => "set_speculate",
+            InterfaceCommand::EvaluateAllPlacements(_) => "evaluate_all_placements",
+            InterfaceCommand::IntegrityCheck(_) => "integrity_check",
+            InterfaceCommand::Cells(_) => "cells",
+            InterfaceCommand::Finesse(..) => "finesse",
+            InterfaceCommand::SetOnDanger(_) => "on_danger",
+            InterfaceCommand::PositionKey(_) => "position_key",
+            InterfaceCommand::SetOnCommit(_) => "on_commit",
+            InterfaceCommand::SetBreakpoint(_) => "set_breakpoint",
+            InterfaceCommand::CurrentOptions(_) => "current_options",
+            InterfaceCommand::PlanAhead(..) => "plan_ahead",
+            InterfaceCommand::BestNextPiece(_) => "best_next_piece",
+            InterfaceCommand::EvalAfterGarbage(..) => "eval_after_garbage",
+            InterfaceCommand::FieldDelta(_) => "field_delta",
+            InterfaceCommand::MaxHeight(_) => "max_height",
+            InterfaceCommand::TimingStats(_) => "timing_stats",
+            InterfaceCommand::ForbidPlacements(..) => "forbid_placements",
+            InterfaceCommand::ClearForbidden(_) => "clear_forbidden",
+            InterfaceCommand::SpinSetups(_) => "detect_spin_setups",
+            InterfaceCommand::FumenQuiz(..) => "move_to_fumen_quiz",
+            InterfaceCommand::DownstackPlan(_) => "downstack_plan",
+            InterfaceCommand::HoldComparison(_) => "hold_comparison",
+            InterfaceCommand::ExportGame(_) => "export_game",
+            InterfaceCommand::Holes(_) => "holes"
+        }
+    }
+}
+
+/// What fills a cell of `Worker::cells`: nothing, garbage, or one plus the piece's index in
+/// libtetris's piece order.
+pub(crate) const EMPTY_CELL: u8 = 0;
+pub(crate) const GARBAGE_CELL: u8 = 8;
+
+/// A move, the search info cold_clear provided with it, and a summary derived from that info.
+pub(crate) type MoveResult = (cold_clear::Move, cold_clear::Info, MoveSummary);
+
+#[derive(Serialize)]
+pub(crate) struct MoveSummary {
+    /// The total number of lines the bot's plan clears, including this move.
+    pub(crate) plan_lines_cleared: u32,
+    pub(crate) hold_reason: HoldReason,
+    /// How many placements of the plan only use pieces the player can see.
+    pub(crate) confirmed_placements: u32,
+    /// Whether the plan goes on past the pieces the player can see.
+    pub(crate) speculative: bool,
+    /// Whether this move isn't a T-spin but improves a T-slot the plan spins into later.
+    pub(crate) sets_up_tspin: bool,
+    /// The garbage the plan sends, divided by how many placements it has.
+    pub(crate) attack_per_piece: Option<f64>,
+    /// Whether the move was played from the committed plan instead of being searched for.
+    pub(crate) from_committed_plan: bool,
+    /// Whether the move soft drops despite `allow_soft_drop`, because no placement that doesn't
+    /// survived.
+    pub(crate) restriction_broken: bool
+}
+
+impl MoveSummary {
+    /// `confirmed_pieces` is how many pieces of the queue, including the current piece, were
+    /// visible to the player when the move was chosen.
+    ///
+    /// `tslot_gain` is how much the move raised the evaluator's T-slot term.
+    fn new(
+        info: &cold_clear::Info,
+        hold_reason: HoldReason,
+        confirmed_pieces: u32,
+        tslot_gain: i32
+    ) -> Self {
+        let plan = crate::plan(info);
+        let placements = plan.len() as u32;
+        let spins_now = plan.first()
+            .map_or(false, |(location, _)| location.tspin != TspinStatus::None);
+        let spins_later = plan.iter()
+            .skip(1)
+            .any(|(location, _)| location.tspin != TspinStatus::None);
+        Self {
+            plan_lines_cleared: plan.iter()
+                .map(|(_, lock)| lock.cleared_lines.len() as u32)
+                .sum(),
+            hold_reason,
+            confirmed_placements: placements.min(confirmed_pieces),
+            speculative: placements > confirmed_pieces,
+            sets_up_tspin: !spins_now && spins_later && tslot_gain > 0,
+            attack_per_piece: match plan.len() {
+                0 => None,
+                len => {
+                    let attack: u32 = plan.iter().map(|(_, lock)| lock.garbage_sent).sum();
+                    Some(attack as f64 / len as f64)
+                }
+            },
+            from_committed_plan: false,
+            restriction_broken: false
+        }
+    }
+}
+
+/// How often each piece was placed in each rotation and column.
+#[derive(Serialize, Clone)]
+pub(crate) struct PieceStats {
+    piece: Piece,
+    placed: u32,
+    /// Indexed by rotation state: North, East, South, West.
+    rotations: [u32; 4],
+    /// Indexed by the leftmost column the piece covered.
+    columns: [u32; 10]
+}
+
+/// Placement counts for every piece, in libtetris's piece order (I, O, T, L, J, S, Z).
+#[derive(Serialize, Clone)]
+pub(crate) struct PlacementStats([PieceStats; 7]);
+
+impl PlacementStats {
+    fn new() -> Self {
+        let stats = |piece| PieceStats { piece, placed: 0, rotations: [0; 4], columns: [0; 10] };
+        PlacementStats([
+            stats(PIECES[0]),
+            stats(PIECES[1]),
+            stats(PIECES[2]),
+            stats(PIECES[3]),
+            stats(PIECES[4]),
+            stats(PIECES[5]),
+            stats(PIECES[6])
+        ])
+    }
+
+    fn record(&mut self, placement: &FallingPiece) {
+        let PieceState(piece, rotation) = placement.kind;
+        let stats = &mut self.0[PIECES.iter().position(|&p| p == piece).unwrap()];
+        let column = placement.cells().iter().map(|&(x, _)| x).min().unwrap();
+        stats.placed += 1;
+        stats.rotations[ROTATIONS.iter().position(|&r| r == rotation).unwrap()] += 1;
+        stats.columns[column as usize] += 1;
+    }
+}
+
+/// How long the worker took to provide moves, in milliseconds.
+#[derive(Serialize)]
+pub(crate) struct TimingStats {
+    moves: u32,
+    min_ms: f64,
+    max_ms: f64,
+    mean_ms: f64,
+    /// The time 95% of moves took at most, by the nearest-rank method.
+    p95_ms: f64
+}
+
+impl TimingStats {
+    /// Summarizes `times`, or returns `None` if there are none.
+    fn new(times: &[f64]) -> Option<Self> {
+        if times.is_empty() {
+            return None;
+        }
+        let mut sorted = times.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+        Some(TimingStats {
+            moves: sorted.len() as u32,
+            min_ms: sorted[0],
+            max_ms: sorted[sorted.len() - 1],
+            mean_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p95_ms: sorted[rank - 1]
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct ActivePiece {
+    /// The piece the next move places if it doesn't hold.
+    current: Option<Piece>,
+    hold: Option<Piece>
+}
+
+/// The board after a placement, and the move that made it, for building replays.
+#[derive(Serialize)]
+struct CommitRecord<'a> {
+    field: [[bool; 10]; 40],
+    hold: Option<Piece>,
+    queue: Vec<Piece>,
+    b2b: bool,
+    combo: u32,
+    #[serde(rename = "move")]
+    mv: &'a cold_clear::Move
+}
+
+/// A placement as `export_game` lists it.
+#[derive(Serialize, Clone)]
+pub(crate) struct GamePlacement {
+    piece: Piece,
+    rotation: RotationState,
+    x: i32,
+    y: i32,
+    hold: bool
+}
+
+#[derive(Serialize)]
+pub(crate) struct CellChange {
+    row: usize,
+    col: usize,
+    filled: bool
+}
+
+/// How many commands the debug log remembers.
+const DEBUG_LOG_LENGTH: usize = 256;
+
+#[derive(Serialize, Clone)]
+pub(crate) struct DebugEntry {
+    command: &'static str,
+    /// A hex-encoded hash of the mirrored board after the command was processed.
+    board_hash: String
+}
+
+/// Hashes everything that affects the bot's decisions: the field, hold, queue and counters.
+fn board_hash(board: &Board) -> String {
+    let mut hasher = DefaultHasher::new();
+    board.get_field().hash(&mut hasher);
+    board.hold_piece.map(|piece| piece as u8).hash(&mut hasher);
+    for piece in board.next_queue() {
+        (piece as u8).hash(&mut hasher);
+    }
+    board.b2b_bonus.hash(&mut hasher);
+    board.combo.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+enum WorkerState {
+    Initializing(u32),
+    Ready(cold_clear::Interface)
+}
+
+/// State the worker publishes so the interface can read it without waiting for the worker to get
+/// through its command queue.
+pub(crate) struct WorkerStatus {
+    pub(crate) death: Cell<Option<DeathReason>>,
+    /// When the worker last finished processing a command, as a `Date.now()` timestamp.
+    pub(crate) last_activity: Cell<f64>
+}
+
+impl WorkerStatus {
+    pub(crate) fn new() -> Self {
+        Self {
+            death: Cell::new(None),
+            last_activity: Cell::new(js_sys::Date::now())
+        }
+    }
+}
+
+/// Owns the cold_clear interface and everything needed to relaunch it.
+///
+/// cold_clear can't change its evaluator or options once launched, so those changes are made by
+/// launching a new interface from `board`, which mirrors the board the bot is playing on.
+pub(crate) struct Worker {
+    worker_uri: String,
+    options: LaunchOptions,
+    evaluator: Standard,
+    aggression: Option<f64>,
+    board: Board,
+    state: WorkerState,
+    status: Rc<WorkerStatus>,
+    /// Only present if debugging was enabled at launch.
+    debug_log: Option<VecDeque<DebugEntry>>,
+    /// Called once the bot first becomes ready, then discarded.
+    on_ready: Option<js_sys::Function>,
+    /// The forced analysis lines that are still ahead of the bot, starting from its current piece.
+    forced_lines: Vec<Vec<FallingPiece>>,
+    /// Tallies of every placement since launch or the last new game.
+    placement_stats: PlacementStats,
+    /// What the node limits are multiplied by, which is only ever changed by `adaptive_nodes`.
+    node_factor: u32,
+    /// What the survival weights are multiplied by, which is only ever changed by
+    /// `defensive_under_pressure`.
+    defense_factor: u32,
+    /// What fills each cell of the mirrored board, which libtetris doesn't keep track of.
+    cells: [[u8; 10]; 40],
+    /// Called when the bot's plan tops out within the given number of placements.
+    on_danger: Option<(js_sys::Function, u32)>,
+    /// Called with a `CommitRecord` after every placement.
+    on_commit: Option<js_sys::Function>,
+    /// Called, then discarded, once a placement leaves a board matching the breakpoint.
+    breakpoint: Option<(Breakpoint, js_sys::Function)>,
+    /// The field as of the last `field_delta` call, in the client's row order.
+    reported_field: Option<[[bool; 10]; 40]>,
+    /// The placements still to be played from the last plan, with the info it came with.
+    committed_plan: Option<(cold_clear::Info, VecDeque<FallingPiece>)>,
+    /// How long each move since launch or the last reset took to provide, in milliseconds.
+    think_times: Vec<f64>,
+    /// Placements the bot must not play, compared by the cells they fill.
+    forbidden: Vec<FallingPiece>,
+    /// Every placement since launch or the last new game, in order.
+    game: Vec<GamePlacement>,
+    /// Pieces beyond `max_queue_consider`, which the bot is given as it places pieces.
+    withheld: VecDeque<Piece>
+}
+
+/// Mirrors the bookkeeping cold_clear does on its own board when it plays a move.
+pub(crate) fn apply_move(board: &mut Board, mv: &cold_clear::Move) -> LockResult {
+    let next = board.advance_queue().unwrap();
+    if mv.hold && board.hold(next).is_none() {
+        board.advance_queue();
+    }
+    board.lock_piece(mv.expected_location)
+}
+
+/// How much `adaptive_nodes` and `defensive_under_pressure` scale the bot's node limits and
+/// survival weights by when `incoming` lines of garbage are coming: 1 for up to 3 lines, 2 for 4
+/// to 7 lines, and 3 for 8 lines or more.
+///
+/// The factor only changes in steps, since changing it means relaunching the bot.
+fn pressure_factor(incoming: u32) -> u32 {
+    1 + (incoming / 4).min(2)
+}
+
+/// Search info for a move the worker picked without searching, whose plan is only that move.
+fn unsearched_info(outcome: &analysis::Outcome) -> cold_clear::Info {
+    cold_clear::Info::Normal(cold_clear::NormalInfo {
+        nodes: 0,
+        depth: 0,
+        original_rank: 0,
+        plan: vec![(outcome.placement.location, outcome.lock.clone())]
+    })
+}
+
+/// Works out why the bot died on `board`, given that it could not provide a move.
+fn death_reason(board: &Board, options: &cold_clear::Options) -> DeathReason {
+    match board.next_queue().next() {
+        Some(piece) if options.spawn_rule.spawn(piece, board).is_none() => DeathReason::BlockOut,
+        _ => DeathReason::TopOut
+    }
+}
+
+impl Worker {
+    pub(crate) fn new(
+        worker_uri: String,
+        options: LaunchOptions,
+        evaluator: Standard,
+        on_ready: Option<js_sys::Function>,
+        status: Rc<WorkerStatus>
+    ) -> Self {
+        let pieces_left = match (options.immediate_start, options.cold_clear.use_hold) {
+            (true, _) => 0,
+            (false, true) => 3,
+            (false, false) => 2
+        };
+        let debug = options.debug;
+        Self {
+            worker_uri,
+            options,
+            evaluator,
+            aggression: None,
+            board: Board::new(),
+            state: WorkerState::Initializing(pieces_left),
+            status,
+            debug_log: if debug { Some(VecDeque::new()) } else { None },
+            on_ready,
+            forced_lines: vec![],
+            placement_stats: PlacementStats::new(),
+            node_factor: 1,
+            defense_factor: 1,
+            cells: [[EMPTY_CELL; 10]; 40],
+            on_danger: None,
+            on_commit: None,
+            breakpoint: None,
+            reported_field: None,
+            committed_plan: None,
+            think_times: vec![],
+            forbidden: vec![],
+            game: vec![],
+            withheld: VecDeque::new()
+        }
+    }
+
+    pub(crate) async fn run(mut self, recv: Receiver<InterfaceCommand>) {
+        if let WorkerState::Initializing(0) = self.state {
+            self.launch().await;
+        }
+        while let Some(command) = recv.recv().await {
+            let name = command.name();
+            self.handle(command).await;
+            self.status.last_activity.set(js_sys::Date::now());
+            if let Some(log) = &mut self.debug_log {
+                if log.len() == DEBUG_LOG_LENGTH {
+                    log.pop_front();
+                }
+                log.push_back(DebugEntry { command: name, board_hash: board_hash(&self.board) });
+            }
+        }
+    }
+
+    /// The evaluator the bot should currently be using, with runtime adjustments applied.
+    fn live_evaluator(&self) -> Standard {
+        let mut evaluator = self.evaluator.clone();
+        if self.options.restricted_pieces() {
+            evaluator.use_bag = false;
+        }
+        if let Some(level) = self.aggression {
+            crate::tuning::apply_aggression(&mut evaluator, level);
+        }
+        if self.defense_factor > 1 {
+            crate::tuning::apply_defense(&mut evaluator, self.defense_factor as f64);
+        }
+        if self.options.speed_mode {
+            crate::tuning::apply_speed_mode(&mut evaluator);
+        }
+        if !self.options.reward_b2b {
+            crate::tuning::remove_b2b_reward(&mut evaluator);
+        }
+        if !self.options.reward_combo {
+            crate::tuning::remove_combo_reward(&mut evaluator);
+        }
+        if !self.options.preferred_wells.is_empty() {
+            crate::tuning::apply_preferred_wells(&mut evaluator, &self.options.preferred_wells);
+        }
+        evaluator
+    }
+
+    /// The options the bot should currently be using, with runtime adjustments applied.
+    fn live_options(&self) -> cold_clear::Options {
+        let mut options = self.options.cold_clear;
+        options.min_nodes = options.min_nodes.saturating_mul(self.node_factor);
+        options.max_nodes = options.max_nodes.saturating_mul(self.node_factor);
+        options
+    }
+
+    /// Launches a fresh cold_clear interface from the mirrored board.
+    async fn launch(&mut self) {
+        let mut interface = cold_clear::Interface::launch(
+            &self.worker_uri,
+            self.board.clone(),
+            self.live_options(),
+            self.live_evaluator()
+        ).await;
+        for line in &self.forced_lines {
+            interface.force_analysis_line(line.clone());
+        }
+        self.state = WorkerState::Ready(interface);
+        if let Some(on_ready) = self.on_ready.take() {
+            // There is nowhere to report an exception thrown by the callback, so it is ignored.
+            on_ready.call0(&wasm_bindgen::JsValue::NULL).ok();
+        }
+    }
+
+    /// Searches the mirrored board with a separate interface and returns up to `pieces` placements
+    /// of the resulting plan, leaving the running bot as it is.
+    ///
+    /// The plan is empty if the bot isn't ready or has too few pieces to pick a move, since the
+    /// separate interface would only wait for pieces that are never given to it.
+    async fn plan_ahead(&self, pieces: u32, incoming: u32) -> Vec<FallingPiece> {
+        if let WorkerState::Initializing(_) = self.state {
+            return vec![];
+        }
+        if self.queue_too_short() {
+            return vec![];
+        }
+        let mut interface = cold_clear::Interface::launch(
+            &self.worker_uri,
+            self.board.clone(),
+            self.live_options(),
+            self.live_evaluator()
+        ).await;
+        interface.request_next_move(incoming);
+        match interface.next_move().await {
+            Some((_, info)) => crate::plan(&info)
+                .iter()
+                .take(pieces as usize)
+                .map(|&(location, _)| location)
+                .collect(),
+            None => vec![]
+        }
+    }
+
+    /// Picks up changed options or evaluator weights, throwing away the current search tree.
+    async fn relaunch(&mut self) {
+        self.committed_plan = None;
+        if let WorkerState::Ready(_) = self.state {
+            self.launch().await;
+        }
+    }
+
+    /// Asks the running bot for a move and updates the mirrored board to match.
+    ///
+    /// If `no_speculation` is set while speculation is enabled, the bot is relaunched without
+    /// speculation for this move only, and relaunched with it again afterwards. With
+    /// `adaptive_nodes` or `defensive_under_pressure`, the bot is also relaunched when `incoming`
+    /// calls for other node limits or survival weights.
+    async fn next_move(
+        &mut self,
+        incoming: u32,
+        no_speculation: bool
+    ) -> Option<MoveResult> {
+        let node_factor = match self.options.adaptive_nodes {
+            true => pressure_factor(incoming),
+            false => 1
+        };
+        let defense_factor = match self.options.defensive_under_pressure {
+            true => pressure_factor(incoming),
+            false => 1
+        };
+        let suspend_speculation = no_speculation && self.options.cold_clear.speculate;
+        if suspend_speculation {
+            self.options.cold_clear.speculate = false;
+        }
+        let pressure_changed = node_factor != self.node_factor
+            || defense_factor != self.defense_factor;
+        if suspend_speculation || pressure_changed {
+            self.node_factor = node_factor;
+            self.defense_factor = defense_factor;
+            self.relaunch().await;
+        }
+
+        let interface = match &mut self.state {
+            WorkerState::Ready(interface) => interface,
+            WorkerState::Initializing(_) => return None
+        };
+        interface.request_next_move(incoming);
+        let result = match interface.next_move().await {
+            Some((mv, info)) => {
+                self.warn_of_danger(&info);
+                let result = self.play(mv, info).await;
+                if result.is_none() {
+                    // Nothing is allowed, which doesn't mean the bot has died. cold_clear went
+                    // ahead with its own move, which the mirrored board doesn't have.
+                    self.relaunch().await;
+                }
+                result
+            }
+            None => {
+                let result = self.soft_drop_fallback().await;
+                if result.is_none() {
+                    let reason = death_reason(&self.board, &self.options.cold_clear);
+                    self.status.death.set(Some(reason));
+                }
+                result
+            }
+        };
+
+        if suspend_speculation {
+            self.options.cold_clear.speculate = true;
+            self.relaunch().await;
+        }
+        if let Some((mv, info, _)) = &result {
+            self.commit_to_plan(mv, info);
+        }
+        result
+    }
+
+    /// Keeps the placements that follow `mv` in its plan, up to `commit_plan` placements in all,
+    /// unless `mv` isn't the first placement of the plan because of a restriction.
+    fn commit_to_plan(&mut self, mv: &cold_clear::Move, info: &cold_clear::Info) {
+        let plan = crate::plan(info);
+        match plan.first() {
+            Some((location, _)) if analysis::same_placement(location, &mv.expected_location) => {}
+            _ => return
+        }
+        let planned: VecDeque<_> = plan.iter()
+            .skip(1)
+            .take(self.options.commit_plan.saturating_sub(1) as usize)
+            .map(|&(location, _)| location)
+            .collect();
+        if !planned.is_empty() {
+            self.committed_plan = Some((info.clone(), planned));
+        }
+    }
+
+    /// Plays the next placement of the committed plan on the mirrored board, if it is still a
+    /// placement of the current or held piece there and is allowed.
+    /// Otherwise the rest of the plan is thrown away.
+    fn planned_move(&mut self) -> Option<MoveResult> {
+        let (info, mut planned) = self.committed_plan.take()?;
+        let placement = planned.pop_front()?;
+        let outcome = analysis::outcomes(&self.board, &self.options.cold_clear)
+            .into_iter()
+            .find(|outcome| {
+                analysis::same_placement(&outcome.placement.location, &placement)
+                    && self.allowed(&outcome.placement.location, &outcome.board)
+            })?;
+        if !planned.is_empty() {
+            self.committed_plan = Some((info.clone(), planned));
+        }
+
+        let evaluator = self.live_evaluator();
+        let mut mv = outcome.to_move();
+        let hold_reason = analysis::hold_reason(
+            &self.board,
+            &self.options.cold_clear,
+            &evaluator,
+            mv.hold
+        );
+        let confirmed_pieces = self.options.confirmed_pieces(&self.board);
+        let tslots_before = analysis::tslot_value(&self.board, &evaluator);
+        self.board = outcome.board;
+        self.placed(&mv);
+
+        if self.options.instant_gravity {
+            mv.inputs.retain(|&input| input != PieceMovement::SonicDrop);
+        }
+        let tslot_gain = analysis::tslot_value(&self.board, &evaluator) - tslots_before;
+        let mut summary = MoveSummary::new(&info, hold_reason, confirmed_pieces, tslot_gain);
+        summary.from_committed_plan = true;
+        Some((mv, info, summary))
+    }
+
+    /// Has the running bot play a placement of the committed plan too, relaunching it from the
+    /// mirrored board if it would have played something else.
+    ///
+    /// The bot keeps thinking between moves, so it has usually searched enough by the time it is
+    /// asked and answers right away.
+    async fn follow_plan(&mut self, mv: &cold_clear::Move, incoming: u32) {
+        let interface = match &mut self.state {
+            WorkerState::Ready(interface) => interface,
+            WorkerState::Initializing(_) => return
+        };
+        interface.request_next_move(incoming);
+        let agrees = match interface.next_move().await {
+            Some((own, _)) => {
+                own.hold == mv.hold
+                    && analysis::same_placement(&own.expected_location, &mv.expected_location)
+            }
+            None => false
+        };
+        if !agrees {
+            let plan = self.committed_plan.take();
+            self.launch().await;
+            self.committed_plan = plan;
+        }
+    }
+
+    /// Calls the `on_danger` callback if even the plan the bot chose tops out soon.
+    fn warn_of_danger(&self, info: &cold_clear::Info) {
+        let (callback, pieces_ahead) = match &self.on_danger {
+            Some(on_danger) => on_danger,
+            None => return
+        };
+        let death = crate::plan(info)
+            .iter()
+            .take(*pieces_ahead as usize)
+            .position(|(_, lock)| lock.locked_out);
+        if let Some(index) = death {
+            // There is nowhere to report an exception thrown by the callback, so it is ignored.
+            let placements = wasm_bindgen::JsValue::from(index as u32 + 1);
+            callback.call1(&wasm_bindgen::JsValue::NULL, &placements).ok();
+        }
+    }
+
+    /// Updates the mirrored board with the move the bot chose.
+    ///
+    /// If the move is forbidden or breaks a restriction set at launch, it is replaced with the
+    /// placement the evaluator likes best among those that aren't and don't, or `None` if there
+    /// are none. The search info is still the one cold_clear provided for its own move.
+    async fn play(
+        &mut self,
+        mut mv: cold_clear::Move,
+        info: cold_clear::Info
+    ) -> Option<MoveResult> {
+        let evaluator = self.live_evaluator();
+        let hold_reason = analysis::hold_reason(
+            &self.board,
+            &self.options.cold_clear,
+            &evaluator,
+            mv.hold
+        );
+        let confirmed_pieces = self.options.confirmed_pieces(&self.board);
+        let tslots_before = analysis::tslot_value(&self.board, &evaluator);
+        let mut board = self.board.clone();
+        apply_move(&mut board, &mv);
+        if self.allowed(&mv.expected_location, &board) {
+            self.board = board;
+            self.placed(&mv);
+        } else {
+            let candidate = analysis::candidates(&self.board, &self.options.cold_clear, &evaluator)
+                .into_iter()
+                .find(|candidate| {
+                    self.allowed(&candidate.outcome.placement.location, &candidate.outcome.board)
+                })?;
+            mv = candidate.outcome.to_move();
+            self.commit(candidate.outcome).await;
+        }
+
+        if self.options.instant_gravity {
+            mv.inputs.retain(|&input| input != PieceMovement::SonicDrop);
+        }
+        let tslot_gain = analysis::tslot_value(&self.board, &evaluator) - tslots_before;
+        let summary = MoveSummary::new(&info, hold_reason, confirmed_pieces, tslot_gain);
+        Some((mv, info, summary))
+    }
+
+    /// Plays the placement the evaluator likes best with soft drops allowed, for when the bot
+    /// can't find a move that only hard drops, or returns `None` if there is none either.
+    ///
+    /// Placements that top out, are forbidden or break `max_height` are left out. There is no
+    /// search behind the move, so its plan is only the move.
+    async fn soft_drop_fallback(&mut self) -> Option<MoveResult> {
+        match self.options.cold_clear.mode {
+            MovementMode::HardDropOnly => {}
+            _ => return None
+        }
+        let options = cold_clear::Options { mode: MovementMode::ZeroG, ..self.options.cold_clear };
+        let evaluator = self.live_evaluator();
+        let outcome = analysis::candidates(&self.board, &options, &evaluator)
+            .into_iter()
+            .find(|candidate| {
+                !candidate.outcome.lock.locked_out
+                    && self.allowed(&candidate.outcome.placement.location, &candidate.outcome.board)
+            })?
+            .outcome;
+
+        let mv = outcome.to_move();
+        let info = unsearched_info(&outcome);
+        let hold_reason = analysis::hold_reason(&self.board, &options, &evaluator, mv.hold);
+        let confirmed_pieces = self.options.confirmed_pieces(&self.board);
+        let tslots_before = analysis::tslot_value(&self.board, &evaluator);
+        self.commit(outcome).await;
+
+        let tslot_gain = analysis::tslot_value(&self.board, &evaluator) - tslots_before;
+        let mut summary = MoveSummary::new(&info, hold_reason, confirmed_pieces, tslot_gain);
+        summary.restriction_broken = true;
+        Some((mv, info, summary))
+    }
+
+    /// Whether a placement isn't forbidden, and the board after it respects the restrictions set
+    /// at launch.
+    fn allowed(&self, placement: &FallingPiece, board: &Board) -> bool {
+        if self.forbidden(placement) {
+            return false;
+        }
+        match self.options.max_height {
+            Some(max_height) => board.column_heights().iter().all(|&height| height <= max_height),
+            None => true
+        }
+    }
+
+    /// Whether a placement is one `forbid_placements` forbade.
+    fn forbidden(&self, placement: &FallingPiece) -> bool {
+        self.forbidden.iter().any(|forbidden| analysis::same_placement(forbidden, placement))
+    }
+
+    /// Whether the bot has too few pieces to pick a move; see `analysis::queue_too_short`.
+    fn queue_too_short(&self) -> bool {
+        analysis::queue_too_short(&self.board, self.options.cold_clear.use_hold)
+    }
+
+    /// Whether the bot's queue is shorter than `max_queue_consider`.
+    fn queue_has_room(&self) -> bool {
+        match self.options.max_queue_consider {
+            Some(cap) => (self.board.next_queue().count() as u32) < cap,
+            None => true
+        }
+    }
+
+    /// Plays a placement the bot didn't necessarily choose itself.
+    ///
+    /// cold_clear can only advance its state by playing its own moves, so this relaunches it from
+    /// the board after the placement.
+    async fn commit(&mut self, outcome: analysis::Outcome) {
+        let mv = outcome.to_move();
+        self.board = outcome.board;
+        self.placed(&mv);
+        self.relaunch().await;
+    }
+
+    /// Records a placement the mirrored board has just been updated with.
+    ///
+    /// Forced lines that didn't start with the move's placement are dropped, and the placement
+    /// itself is removed from those that did.
+    fn placed(&mut self, mv: &cold_clear::Move) {
+        let placement = &mv.expected_location;
+        self.placement_stats.record(placement);
+        self.game.push(GamePlacement {
+            piece: placement.kind.0,
+            rotation: placement.kind.1,
+            x: placement.x,
+            y: placement.y,
+            hold: mv.hold
+        });
+
+        let kind = PIECES.iter().position(|&p| p == placement.kind.0).unwrap() as u8 + 1;
+        for &(x, y) in &placement.cells() {
+            self.cells[y as usize][x as usize] = kind;
+        }
+        // libtetris has just cleared the same rows from the board.
+        let kept: Vec<_> = self.cells.iter()
+            .filter(|row| row.contains(&EMPTY_CELL))
+            .copied()
+            .collect();
+        self.cells = [[EMPTY_CELL; 10]; 40];
+        self.cells[..kept.len()].copy_from_slice(&kept);
+
+        self.forced_lines.retain(|line| {
+            line.len() > 1 && analysis::same_placement(&line[0], placement)
+        });
+        for line in &mut self.forced_lines {
+            line.remove(0);
+        }
+
+        while self.queue_has_room() {
+            let piece = match self.withheld.pop_front() {
+                Some(piece) => piece,
+                None => break
+            };
+            self.board.add_next_piece(piece);
+            if let WorkerState::Ready(interface) = &mut self.state {
+                interface.add_next_piece(piece);
+            }
+        }
+
+        if let Some(on_commit) = &self.on_commit {
+            let record = CommitRecord {
+                field: self.options.coordinate_origin.field(self.board.get_field()),
+                hold: self.board.hold_piece,
+                queue: self.board.next_queue().collect(),
+                b2b: self.board.b2b_bonus,
+                combo: self.board.combo,
+                mv
+            };
+            let record = wasm_bindgen::JsValue::from_serde(&record).unwrap();
+            // There is nowhere to report an exception thrown by the callback, so it is ignored.
+            on_commit.call1(&wasm_bindgen::JsValue::NULL, &record).ok();
+        }
+
+        let hit = match &self.breakpoint {
+            Some((breakpoint, _)) => breakpoint.matches(&self.board),
+            None => false
+        };
+        if hit {
+            let (_, on_breakpoint) = self.breakpoint.take().unwrap();
+            let board_key = crate::encoding::position_key(&self.board);
+            on_breakpoint.call1(&wasm_bindgen::JsValue::NULL, &board_key.into()).ok();
+        }
+    }
+
+    async fn handle(&mut self, command: InterfaceCommand) {
+        match command {
+            InterfaceCommand::Reset { cells, b2b, combo, new_game } => {
+                let mut field = [[false; 10]; 40];
+                for (row, dest) in cells.iter().zip(field.iter_mut()) {
+                    for (&cell, filled) in row.iter().zip(dest.iter_mut()) {
+                        *filled = cell != EMPTY_CELL;
+                    }
+                }
+                self.cells = cells;
+                self.status.death.set(None);
+                self.board.set_field(field);
+                self.board.b2b_bonus = b2b;
+                self.board.combo = combo;
+                self.forced_lines.clear();
+                self.committed_plan = None;
+                self.think_times.clear();
+                if new_game {
+                    self.placement_stats = PlacementStats::new();
+                    self.game.clear();
+                }
+                if let WorkerState::Ready(interface) = &mut self.state {
+                    interface.reset(field, b2b, combo);
+                }
+            }
+            InterfaceCommand::SetCounters { b2b, combo, done } => {
+                self.board.b2b_bonus = b2b;
+                self.board.combo = combo;
+                self.committed_plan = None;
+                if let WorkerState::Ready(interface) = &mut self.state {
+                    interface.reset(self.board.get_field(), b2b, combo);
+                }
+                done.resolve(()).unwrap();
+            }
+            InterfaceCommand::NewPiece(piece) => {
+                if !self.withheld.is_empty() || !self.queue_has_room() {
+                    self.withheld.push_back(piece);
+                } else {
+                    self.board.add_next_piece(piece);
+                    match &mut self.state {
+                        WorkerState::Initializing(pieces_left) => {
+                            *pieces_left -= 1;
+                            if *pieces_left == 0 {
+                                self.launch().await;
+                            }
+                        }
+                        WorkerState::Ready(interface) => {
+                            interface.add_next_piece(piece);
+                        }
+                    }
+                }
+            }
+            InterfaceCommand::NextMove { incoming, no_speculation, send } => {
+                if let WorkerState::Ready(_) = self.state {
+                    if self.queue_too_short() {
+                        // cold_clear would wait for a piece, which can only be added once this
+                        // command has been handled.
+                        send.resolve(None).unwrap();
+                    } else if let Some(result) = self.planned_move() {
+                        let mv = result.0.clone();
+                        self.think_times.push(0.0);
+                        send.resolve(Some(result)).unwrap();
+                        self.follow_plan(&mv, incoming).await;
+                    } else {
+                        let start = js_sys::Date::now();
+                        let result = self.next_move(incoming, no_speculation).await;
+                        if result.is_some() {
+                            self.think_times.push(js_sys::Date::now() - start);
+                        }
+                        send.resolve(result).unwrap();
+                    }
+                }
+            }
+            InterfaceCommand::ForceAnalysisLine(line) => {
+                if let WorkerState::Ready(interface) = &mut self.state {
+                    interface.force_analysis_line(line.clone());
+                }
+                if !line.is_empty() {
+                    self.forced_lines.push(line);
+                }
+            }
+            InterfaceCommand::SetAggression(level) => {
+                self.aggression = Some(level);
+                self.relaunch().await;
+            }
+            InterfaceCommand::SetSpeculate(enabled, done) => {
+                // Speculation assumes a 7-bag, so it stays off with a restricted piece set.
+                let enabled = enabled && !self.options.restricted_pieces();
+                if enabled != self.options.cold_clear.speculate {
+                    self.options.cold_clear.speculate = enabled;
+                    self.relaunch().await;
+                }
+                done.resolve(()).unwrap();
+            }
+            InterfaceCommand::ExportEvaluator(send) => {
+                // The other adjustments come from launch options, which would apply them again.
+                let mut evaluator = self.evaluator.clone();
+                if let Some(level) = self.aggression {
+                    crate::tuning::apply_aggression(&mut evaluator, level);
+                }
+                send.resolve(evaluator).unwrap();
+            }
+            InterfaceCommand::RankMove { placement, commit, send } => {
+                let mut candidates = analysis::candidates(
+                    &self.board,
+                    &self.options.cold_clear,
+                    &self.live_evaluator()
+                );
+                let count = candidates.len();
+                let found = candidates.iter()
+                    .position(|c| analysis::same_placement(&c.outcome.placement.location, &placement));
+                let rank = found.map(|rank| MoveRank {
+                    rank,
+                    eval: candidates[rank].eval,
+                    candidates: count
+                });
+                send.resolve(rank).unwrap();
+                if let (Some(index), true) = (found, commit) {
+                    let candidate = candidates.swap_remove(index);
+                    self.commit(candidate.outcome).await;
+                }
+            }
+            InterfaceCommand::Holes(send) => {
+                let mut holes = analysis::holes(&self.board);
+                let origin = self.options.coordinate_origin;
+                for (x, column) in holes.iter_mut().enumerate() {
+                    for hole in column {
+                        hole.row = origin.cell((x as i32, hole.row)).1;
+                    }
+                }
+                send.resolve(holes).unwrap();
+            }
+            InterfaceCommand::ExportGame(send) => {
+                send.resolve(self.game.clone()).unwrap();
+            }
+            InterfaceCommand::HoldComparison(send) => {
+                let mut comparison = analysis::hold_comparison(
+                    &self.board,
+                    &self.options.cold_clear,
+                    &self.live_evaluator()
+                );
+                let origin = self.options.coordinate_origin;
+                for placement in comparison.play_now.iter_mut().chain(&mut comparison.hold) {
+                    placement.step.cells = origin.cells(placement.step.cells);
+                }
+                send.resolve(comparison).unwrap();
+            }
+            InterfaceCommand::EvaluateAllPlacements(send) => {
+                let candidates = analysis::candidates(
+                    &self.board,
+                    &self.options.cold_clear,
+                    &self.live_evaluator()
+                );
+                let origin = self.options.coordinate_origin;
+                let placements = candidates.iter()
+                    .map(|candidate| {
+                        let mut placement = ScoredPlacement::new(candidate);
+                        placement.step.cells = origin.cells(placement.step.cells);
+                        placement
+                    })
+                    .collect();
+                send.resolve(placements).unwrap();
+            }
+            InterfaceCommand::PositionKey(send) => {
+                send.resolve(crate::encoding::position_key(&self.board)).unwrap();
+            }
+            InterfaceCommand::SpinSetups(send) => {
+                let counts = analysis::tslot_counts(&self.board, &self.live_evaluator());
+                send.resolve(counts).unwrap();
+            }
+            InterfaceCommand::ForbidPlacements(placements, done) => {
+                self.forbidden.extend(placements);
+                done.resolve(()).unwrap();
+            }
+            InterfaceCommand::ClearForbidden(done) => {
+                self.forbidden.clear();
+                done.resolve(()).unwrap();
+            }
+            InterfaceCommand::TimingStats(send) => {
+                send.resolve(TimingStats::new(&self.think_times)).unwrap();
+            }
+            InterfaceCommand::MaxHeight(send) => {
+                send.resolve(*self.board.column_heights().iter().max().unwrap()).unwrap();
+            }
+            InterfaceCommand::FieldDelta(send) => {
+                let field = self.options.coordinate_origin.field(self.board.get_field());
+                let mut changes = vec![];
+                for (row, cells) in field.iter().enumerate() {
+                    for (col, &filled) in cells.iter().enumerate() {
+                        let reported = self.reported_field.map(|reported| reported[row][col]);
+                        if reported != Some(filled) {
+                            changes.push(CellChange { row, col, filled });
+                        }
+                    }
+                }
+                self.reported_field = Some(field);
+                send.resolve(changes).unwrap();
+            }
+            InterfaceCommand::EvalAfterGarbage(holes, send) => {
+                let evaluator = self.live_evaluator();
+                let mut board = self.board.clone();
+                let delta = match analysis::add_garbage(&mut board, &holes) {
+                    true => Some(
+                        analysis::board_value(&board, &evaluator)
+                            - analysis::board_value(&self.board, &evaluator)
+                    ),
+                    false => None
+                };
+                send.resolve(delta).unwrap();
+            }
+            InterfaceCommand::BestNextPiece(send) => {
+                let values = analysis::piece_values(
+                    &self.board,
+                    &self.options.cold_clear,
+                    &self.live_evaluator()
+                );
+                send.resolve(values).unwrap();
+            }
+            InterfaceCommand::PlanAhead(pieces, send) => {
+                send.resolve(self.plan_ahead(pieces, 0).await).unwrap();
+            }
+            InterfaceCommand::DownstackPlan(send) => {
+                let mut plan = self.plan_ahead(u32::MAX, 0).await;
+                let length = analysis::downstack_length(&self.board, &self.cells, &plan);
+                send.resolve(length.map(|length| {
+                    plan.truncate(length);
+                    plan
+                })).unwrap();
+            }
+            InterfaceCommand::FumenQuiz(incoming, send) => {
+                let quiz = self.plan_ahead(1, incoming).await
+                    .first()
+                    .and_then(|solution| crate::fumen::quiz(&self.board, &self.cells, solution));
+                send.resolve(quiz).unwrap();
+            }
+            InterfaceCommand::CurrentOptions(send) => {
+                send.resolve(self.options.clone()).unwrap();
+            }
+            InterfaceCommand::SetBreakpoint(breakpoint) => {
+                self.breakpoint = breakpoint;
+            }
+            InterfaceCommand::SetOnCommit(on_commit) => {
+                self.on_commit = on_commit;
+            }
+            InterfaceCommand::SetOnDanger(on_danger) => {
+                self.on_danger = on_danger;
+            }
+            InterfaceCommand::Finesse(placement, send) => {
+                let finesse = analysis::finesse(&self.board, &self.options.cold_clear, &placement);
+                send.resolve(finesse).unwrap();
+            }
+            InterfaceCommand::Cells(send) => {
+                send.resolve(self.options.coordinate_origin.field(self.cells)).unwrap();
+            }
+            InterfaceCommand::IntegrityCheck(send) => {
+                let violations = analysis::integrity_violations(
+                    &self.board,
+                    &self.options.cold_clear,
+                    !self.options.restricted_pieces()
+                );
+                send.resolve(violations).unwrap();
+            }
+            InterfaceCommand::WellColumn(send) => {
+                send.resolve(analysis::well_column(&self.board)).unwrap();
+            }
+            InterfaceCommand::DebugLog(send) => {
+                let log = self.debug_log.as_ref().map(|log| log.iter().cloned().collect());
+                send.resolve(log).unwrap();
+            }
+            InterfaceCommand::EvaluateCurrent(send) => {
+                let value = analysis::board_value(&self.board, &self.live_evaluator());
+                send.resolve(value).unwrap();
+            }
+            InterfaceCommand::SetEvaluator(evaluator) => {
+                // `Standard` can't be compared directly, but its serialized form can.
+                let unchanged = serde_json::to_string(&evaluator).unwrap()
+                    == serde_json::to_string(&self.evaluator).unwrap();
+                if !unchanged {
+                    self.evaluator = evaluator;
+                    self.relaunch().await;
+                }
+            }
+            InterfaceCommand::FindPerfectClear(max_placements, send) => {
+                let mut solution = analysis::perfect_clear(
+                    &self.board,
+                    &self.options.cold_clear,
+                    max_placements
+                ).await;
+                for step in solution.iter_mut().flatten() {
+                    step.cells = self.options.coordinate_origin.cells(step.cells);
+                }
+                send.resolve(solution).unwrap();
+            }
+            InterfaceCommand::IsPlacementLegal(placement, send) => {
+                let legal = analysis::outcomes(&self.board, &self.options.cold_clear)
+                    .iter()
+                    .any(|outcome| analysis::same_placement(&outcome.placement.location, &placement));
+                send.resolve(legal).unwrap();
+            }
+            InterfaceCommand::ObserveMove(placement, send) => {
+                let outcome = analysis::outcomes(&self.board, &self.options.cold_clear)
+                    .into_iter()
+                    .find(|outcome| analysis::same_placement(&outcome.placement.location, &placement));
+                send.resolve(outcome.is_some()).unwrap();
+                if let Some(outcome) = outcome {
+                    self.commit(outcome).await;
+                }
+            }
+            InterfaceCommand::ForcedLines(send) => {
+                send.resolve(self.forced_lines.clone()).unwrap();
+            }
+            InterfaceCommand::PlacementStats(send) => {
+                send.resolve(self.placement_stats.clone()).unwrap();
+            }
+            InterfaceCommand::StrengthEstimate(send) => {
+                let value = analysis::board_value(&self.board, &self.live_evaluator());
+                send.resolve(analysis::strength(value)).unwrap();
+            }
+            InterfaceCommand::ActivePiece(send) => {
+                send.resolve(ActivePiece {
+                    current: self.board.next_queue().next(),
+                    hold: self.board.hold_piece
+                }).unwrap();
+            }
+            InterfaceCommand::BagRemaining(send) => {
+                let remaining = PIECES.iter()
+                    .copied()
+                    .filter(|&piece| self.board.bag.contains(piece))
+                    .collect();
+                send.resolve(remaining).unwrap();
+            }
+        }
+    }
+}
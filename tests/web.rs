@@ -0,0 +1,90 @@
+//! Tests of the JavaScript interface, run in a browser with `wasm-pack test --headless --chrome`.
+
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+use cold_clear_js::CCInterface;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn dropping_rejects_pending_promises() {
+    let options = JsValue::from_serde(&cold_clear::Options::default()).unwrap();
+    let evaluator = JsValue::from_serde(&cold_clear::evaluation::Standard::default()).unwrap();
+    // The bot waits for its first pieces before starting a worker, so none is needed here.
+    let interface = CCInterface::launch("unused.js".to_owned(), options, evaluator, None).unwrap();
+    let plan = interface.plan_ahead(1);
+    let timing = interface.timing_stats();
+    drop(interface);
+
+    for promise in &[plan, timing] {
+        let error: js_sys::Error = JsFuture::from(promise.clone())
+            .await
+            .unwrap_err()
+            .dyn_into()
+            .unwrap();
+        assert!(String::from(error.message()).contains("DISPOSED"));
+    }
+}
+
+/// Waits for `ms` milliseconds, letting the worker task run in the meantime.
+async fn sleep(ms: u32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let set_timeout: js_sys::Function =
+            js_sys::Reflect::get(&js_sys::global(), &"setTimeout".into())
+                .unwrap()
+                .dyn_into()
+                .unwrap();
+        set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(ms)).unwrap();
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn dropping_mid_think_rejects_the_move() {
+    // A worker has to be started from a script that calls `_web_worker_entry_point`, which the test
+    // runner doesn't serve, so its URI is given when building the tests. Without one, there is
+    // nothing to test.
+    let worker_uri = match option_env!("CC_TEST_WORKER_URI") {
+        Some(uri) => uri.to_owned(),
+        None => return
+    };
+    let mut options = cold_clear::Options::default();
+    // Enough that the search is still going when the handle is dropped.
+    options.min_nodes = 1_000_000;
+    options.max_nodes = 2_000_000;
+    let options = JsValue::from_serde(&options).unwrap();
+    let evaluator = JsValue::from_serde(&cold_clear::evaluation::Standard::default()).unwrap();
+    let mut interface = CCInterface::launch(worker_uri, options, evaluator, None).unwrap();
+    for piece in &["T", "I", "O", "L", "J", "S", "Z"] {
+        interface.add_next_piece(JsValue::from_str(piece)).unwrap();
+    }
+    let mv = interface.next_move(0, None);
+    sleep(200).await;
+    drop(interface);
+
+    let error: js_sys::Error = JsFuture::from(mv).await.unwrap_err().dyn_into().unwrap();
+    assert!(String::from(error.message()).contains("DISPOSED"));
+}
+
+#[wasm_bindgen_test]
+async fn a_game_too_short_to_hold_in_ends_right_away() {
+    let mut options = cold_clear::Options::default();
+    options.use_hold = true;
+    let options = JsValue::from_serde(&options).unwrap();
+    let evaluator = JsValue::from_serde(&cold_clear::evaluation::Standard::default()).unwrap();
+    let on_move = js_sys::Function::new_no_args("throw new Error('no move was expected')");
+    // A single piece can't be held for another, so the game ends before a worker is needed.
+    let promise =
+        CCInterface::benchmark_stream("unused.js".to_owned(), options, evaluator, 1, 0, on_move)
+            .unwrap();
+    let summary = JsFuture::from(promise).await.unwrap();
+    let placed = js_sys::Reflect::get(&summary, &"pieces_placed".into()).unwrap();
+    assert_eq!(placed.as_f64(), Some(0.0));
+    let dead = js_sys::Reflect::get(&summary, &"dead".into()).unwrap();
+    assert_eq!(dead.as_bool(), Some(false));
+}